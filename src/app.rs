@@ -3,9 +3,10 @@ use std::
 ;
 
 use eframe::egui::{self, load::SizedTexture, ColorImage, ComboBox, DragValue, Image, ImageData, ScrollArea, TextureOptions, Ui};
+use fontdue::LineMetrics;
 
 use crate::{
-    font_face::{FontFace, FontFaceError}, rasterization::{RasterManip, RasterizationProperty}, renderer::{generate_render_data, write_image, RenderData, RenderDirection, RenderInfo, RenderLayout, RenderSettings, RendererError}
+    bdf::write_bdf, c_header::{write_c_header, PackingOrder}, channel_pack::{write_channel_pack, PackedChannel, CHANNEL_NAMES}, effects::{DropShadow, GlyphEffect}, font_face::{FontFace, FontFaceError}, gfx_font::write_gfx_font, godot::write_font_file, mono_font::write_mono_font, psf::write_psf2, rasterization::{CoverageTransfer, Dithering, DownsampleFilter, MissingGlyphPolicy, RasterManip, RasterizationProperty, Rasterizations, Rotation}, renderer::{generate_render_data, BitDepth, ColorMode, OutputFormat, OutputSettings, parse_palette, write_font_notice, write_image, RenderData, RenderDirection, RenderInfo, RenderLayout, RenderSettings, RendererError}, shader::{write_shader, ShaderLanguage}
 };
 
 
@@ -16,25 +17,112 @@ pub struct FontRasterizerApp {
     render_data: RenderData,
     render_info: RenderInfo,
     render: Option<ColorImage>,
+    rasterizations: Rasterizations,
+    h_line_metrics: Option<LineMetrics>,
+    shader_language: ShaderLanguage,
+    output_settings: OutputSettings,
+    palette_text: String,
+    lost_levels: Option<(usize, usize)>,
+    export_godot: bool,
+    c_bit_depth: BitDepth,
+    c_packing: PackingOrder,
+    channel_pack: [Option<PackedChannel>; 4],
 }
 
 
 impl FontRasterizerApp {
     fn load_font(&mut self) {
-        if let Ok(font_face) = get_font_face() {
+        if let Ok(mut font_face) = get_font_face() {
+            if let Some(previous) = &mut self.font_face {
+                font_face.set_fallbacks(previous.take_fallbacks());
+            }
             self.font_face = Some(font_face);
         }
         self.render_font();
     }
 
+    /// Switches to another face of the loaded font collection.
+    fn load_face(&mut self, face_index: u32) {
+        let Some(previous) = &mut self.font_face else {
+            return;
+        };
+        match FontFace::load_face(previous.path_buf(), face_index) {
+            Ok(mut font_face) => {
+                font_face.set_fallbacks(previous.take_fallbacks());
+                self.font_face = Some(font_face);
+                self.render_font();
+            },
+            Err(err) => eprintln!("{}", AppError::FontLoadingError(err)),
+        }
+    }
+
+    fn add_fallback(&mut self) {
+        let Some(font_face) = &mut self.font_face else {
+            return;
+        };
+        match get_font_face() {
+            Ok(fallback) => {
+                font_face.add_fallback(fallback);
+                self.render_font();
+            },
+            Err(AppError::NoFontPath) => (),
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+
+    /// Reloads the fallback at `index` from another face of its font collection.
+    fn load_fallback_face(&mut self, index: usize, face_index: u32) {
+        let Some(font_face) = &mut self.font_face else {
+            return;
+        };
+        let Some(path) = font_face.fallbacks().get(index).map(|f| f.path_buf()) else {
+            return;
+        };
+        match FontFace::load_face(path, face_index) {
+            Ok(fallback) => {
+                font_face.replace_fallback(index, fallback);
+                self.render_font();
+            },
+            Err(err) => eprintln!("{}", AppError::FontLoadingError(err)),
+        }
+    }
+
+    fn remove_fallback(&mut self, index: usize) {
+        if let Some(font_face) = &mut self.font_face {
+            font_face.remove_fallback(index);
+            self.render_font();
+        }
+    }
+
+    /// Characters of the current render grouped by the font in the stack that supplied them.
+    fn glyph_sources(&self) -> Vec<String> {
+        let stack_size = self.font_face.as_ref().map(|f| f.fallbacks().len() + 1).unwrap_or(0);
+        let mut sources = vec![String::new(); stack_size];
+        for raster in &self.rasterizations {
+            if let Some(chars) = sources.get_mut(raster.get_source()) {
+                chars.push(raster.get_char());
+            }
+        }
+        sources
+    }
+
     fn render_font(&mut self) {
         if let Some(font_face) = &self.font_face {
             
             let (
                 h_line_metrics, 
                 v_line_metrics, 
-                mut rasterizations
-            ) = font_face.rasterize(self.render_settings.input.clone(), self.render_settings.render_height);
+                mut rasterizations,
+                missing_chars
+            ) = font_face.rasterize(&self.render_settings);
+
+            rasterizations.apply_transfer(self.render_settings.coverage_transfer);
+            // Glyphs turn around the middle of the line, before effects so shadows keep their direction.
+            let center_y = h_line_metrics.map(|l_m| (l_m.ascent + l_m.descent) / 2.0).unwrap_or(0.0);
+            rasterizations.apply_transform(self.render_settings.transform, center_y);
+            rasterizations.apply_effects(&self.render_settings.effects);
+            rasterizations.apply_dithering(self.render_settings.dithering);
+            rasterizations.apply_inversion(self.render_settings.transform.invert);
 
             if let Some(p) = self.render_settings.dedup_property {
                 rasterizations.dedup_rasters_by(p);
@@ -49,40 +137,148 @@ impl FontRasterizerApp {
             let (render_data, render_info) = generate_render_data(
                 h_line_metrics,
                 v_line_metrics,
-                rasterizations, 
+                rasterizations.clone(), 
                 &self.render_settings
             );
 
             if render_data.renderable() {
+                self.rasterizations = rasterizations;
+                self.h_line_metrics = h_line_metrics;
                 self.render_data = render_data.clone();
-                self.render_info = render_info;
-                self.render = Some(ColorImage::from(render_data));
+                self.render_info = render_info.with_missing_chars(missing_chars);
+                self.render = Some(render_data.preview(self.render_settings.color_mode));
+                self.check_output_levels();
             }
         }
     }
 
+    fn check_output_levels(&mut self) {
+        self.lost_levels = self.output_settings.lost_levels(&self.render_data.to_output(self.render_settings.color_mode));
+    }
+
+    fn texture_name(&self) -> Option<String> {
+        let font_face = self.font_face.as_ref()?;
+        let (cell_width, cell_height) = self.render_info.cell_size();
+        let (cell_h_count, cell_v_count) = self.render_info.cell_count();
+        let (left, right, up, down) = self.render_info.cell_padding();
+        Some(format!(
+            "{}-({}H-{}V)-({}w-{}h)-({}L-{}R-{}U-{}D)", 
+            font_face.stem(), 
+            cell_h_count, 
+            cell_v_count,
+            cell_width, cell_height, 
+            left,
+            right,
+            up,
+            down
+        ))
+    }
+
     fn export_texture(&mut self) {
+        if let (Some(texture_name), Some(font_face)) = (self.texture_name(), &self.font_face) {
+            match write_image(texture_name, &self.render_data.to_output(self.render_settings.color_mode), &self.output_settings, self.render_settings.threshold) {
+                Ok(render_path) => {
+                    if let Err(err) = write_font_notice(&render_path, font_face.metadata()) {
+                        eprintln!("{}", err);
+                    }
+                    if self.export_godot {
+                        let fixed_size = self.render_settings.render_height.round() as usize;
+                        if let Err(err) = write_font_file(&render_path.with_extension("tres"), font_face.metadata(), &self.render_data, &self.render_info, fixed_size) {
+                            eprintln!("{}", err);
+                        }
+                    }
+                },
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+    }
+
+    fn export_shader(&mut self) {
+        if let Some(texture_name) = self.texture_name() {
+            if let Err(err) = write_shader(texture_name, &self.render_info, self.shader_language) {
+                eprintln!("{}", err);
+            }
+        }
+    }
+
+    fn export_bdf(&mut self) {
         if let Some(font_face) = &self.font_face {
-            let (cell_width, cell_height) = self.render_info.cell_size();
-            let (cell_h_count, cell_v_count) = self.render_info.cell_count();
-            let (left, right, up, down) = self.render_info.cell_padding();
-            let texture_name = format!(
-                "{}-({}H-{}V)-({}w-{}h)-({}L-{}R-{}U-{}D)", 
-                font_face.stem(), 
-                cell_h_count, 
-                cell_v_count,
-                cell_width, cell_height, 
-                left,
-                right,
-                up,
-                down
-            );
-            if let Err(err) = write_image(texture_name, &self.render_data) {
+            if let Err(err) = write_bdf(
+                font_face.stem(),
+                font_face.metadata(),
+                self.h_line_metrics,
+                &self.rasterizations,
+                self.render_settings.render_height,
+                self.render_settings.threshold
+            ) {
+                eprintln!("{}", err);
+            }
+        }
+    }
+
+    fn export_psf2(&mut self) {
+        if let (Some(texture_name), Some(font_face)) = (self.texture_name(), &self.font_face) {
+            if let Err(err) = write_psf2(texture_name, font_face.metadata(), &self.render_data, &self.render_info, self.render_settings.threshold) {
+                eprintln!("{}", err);
+            }
+        }
+    }
+
+    fn export_mono_font(&mut self) {
+        if let Some(font_face) = &self.font_face {
+            if let Err(err) = write_mono_font(font_face.stem(), font_face.metadata(), &self.render_data, &self.render_info, self.render_settings.threshold) {
+                eprintln!("{}", err);
+            }
+        }
+    }
+
+    fn export_c_header(&mut self) {
+        if let Some(font_face) = &self.font_face {
+            if let Err(err) = write_c_header(font_face.stem(), font_face.metadata(), &self.render_data, &self.render_info, self.c_bit_depth, self.c_packing, self.render_settings.threshold) {
                 eprintln!("{}", err);
             }
         }
     }
 
+    fn export_gfx_font(&mut self) {
+        if let Some(font_face) = &self.font_face {
+            if let Err(err) = write_gfx_font(
+                font_face.stem(),
+                font_face.metadata(),
+                self.h_line_metrics,
+                &self.rasterizations,
+                self.render_settings.render_height,
+                self.render_settings.threshold
+            ) {
+                eprintln!("{}", err);
+            }
+        }
+    }
+
+    fn assign_channel(&mut self, channel: usize) {
+        if let Some(font_face) = &self.font_face {
+            self.channel_pack[channel] = Some(PackedChannel {
+                font_name: font_face.stem().into(),
+                font_metadata: font_face.metadata().clone(),
+                render_height: self.render_settings.render_height,
+                input: self.render_settings.input.clone(),
+                render_data: self.render_data.clone(),
+                render_info: self.render_info.clone(),
+            });
+        }
+    }
+
+    fn export_channel_pack(&mut self) {
+        let names = self.channel_pack
+            .iter()
+            .flatten()
+            .map(|c| c.font_name.clone())
+            .collect::<Vec<_>>();
+        if let Err(err) = write_channel_pack(format!("{}-packed", names.join("-")), &self.channel_pack, &self.output_settings, self.render_settings.threshold) {
+            eprintln!("{}", err);
+        }
+    }
+
     fn header(&mut self, ui: &mut Ui) {
         ui.vertical(|ui|{
             ui.heading("Font Rasterizer");
@@ -100,15 +296,210 @@ impl FontRasterizerApp {
                 ).clicked() {
                     self.load_font();
                 }
+                if let Some(font_face) = &self.font_face {
+                    if font_face.face_names().len() > 1 {
+                        let mut face_index = font_face.face_index();
+                        let face_names = font_face.face_names().to_vec();
+                        ComboBox::from_id_salt("font-face")
+                            .selected_text(format!("{face_index}: {}", face_names[face_index as usize]))
+                            .show_ui(ui, |ui| {
+                                for (i, name) in face_names.iter().enumerate() {
+                                    ui.selectable_value(&mut face_index, i as u32, format!("{i}: {name}"));
+                                }
+                            });
+                        if face_index != font_face.face_index() {
+                            self.load_face(face_index);
+                        }
+                    }
+                }
                 if self.render.is_some() {
                     ui.separator();
     
                     if ui.button("Export Texture").clicked() {
                         self.export_texture();
                     }
+                    ComboBox::from_id_salt("output-format")
+                        .selected_text(self.output_settings.format.to_string())
+                        .show_ui(ui, |ui| {
+                            for f in [OutputFormat::Png, OutputFormat::Tga, OutputFormat::Dds, OutputFormat::Ktx2, OutputFormat::Qoi] {
+                                if ui.selectable_value(&mut self.output_settings.format, f, f.to_string()).changed() {
+                                    self.check_output_levels();
+                                }
+                            }
+                        });
+                    ui.checkbox(&mut self.export_godot, "Godot FontFile");
                 }
             });
 
+            if self.font_face.is_some() {
+                let sources = self.glyph_sources();
+                let mut remove = None;
+                let mut face_change = None;
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Fallbacks:");
+                    if let Some(font_face) = &self.font_face {
+                        for (i, fallback) in font_face.fallbacks().iter().enumerate() {
+                            let stem = font_face.source_stem(i + 1);
+                            let supplied = sources.get(i + 1).cloned().unwrap_or_default();
+                            if ui.button(format!("{stem} ({}) ✕", supplied.chars().count()))
+                                .on_hover_text(format!("Supplied by {stem}: {supplied}"))
+                                .clicked()
+                            {
+                                remove = Some(i);
+                            }
+                            if fallback.face_names().len() > 1 {
+                                let mut face_index = fallback.face_index();
+                                ComboBox::from_id_salt(("fallback-face", i))
+                                    .selected_text(format!("{face_index}: {}", fallback.face_names()[face_index as usize]))
+                                    .show_ui(ui, |ui| {
+                                        for (j, name) in fallback.face_names().iter().enumerate() {
+                                            ui.selectable_value(&mut face_index, j as u32, format!("{j}: {name}"));
+                                        }
+                                    });
+                                if face_index != fallback.face_index() {
+                                    face_change = Some((i, face_index));
+                                }
+                            }
+                        }
+                    }
+                    if ui.button("Add").clicked() {
+                        self.add_fallback();
+                    }
+                });
+                if let Some(i) = remove {
+                    self.remove_fallback(i);
+                }
+                if let Some((i, face_index)) = face_change {
+                    self.load_fallback_face(i, face_index);
+                }
+            }
+
+            if let Some(font_face) = &self.font_face {
+                ui.collapsing("Font Info", |ui| {
+                    for (label, value) in font_face.metadata().entries() {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.strong(format!("{label}:"));
+                            ui.label(value);
+                        });
+                    }
+                });
+            }
+
+            if self.render.is_some() && self.output_settings.format == OutputFormat::Png {
+                ui.horizontal(|ui| {
+                    let mut indexed = self.output_settings.palette.is_some();
+                    if self.output_settings.palette.is_none() {
+                        ComboBox::from_id_salt("png-bit-depth")
+                            .selected_text(self.output_settings.bit_depth.to_string())
+                            .show_ui(ui, |ui| {
+                                for d in [BitDepth::One, BitDepth::Two, BitDepth::Four, BitDepth::Eight] {
+                                    if ui.selectable_value(&mut self.output_settings.bit_depth, d, d.to_string()).changed() {
+                                        self.check_output_levels();
+                                    }
+                                }
+                            });
+                    }
+                    let mut changed = ui.checkbox(&mut indexed, "Indexed").changed();
+                    if indexed {
+                        if self.palette_text.is_empty() {
+                            self.palette_text = "#000000 #ffffff".into();
+                        }
+                        changed |= ui.text_edit_singleline(&mut self.palette_text)
+                            .on_hover_text("Hex colors, e.g. #000000 #808080 #ffffff")
+                            .changed();
+                    }
+                    if changed {
+                        // Keep the last valid palette while the text is being edited.
+                        if !indexed {
+                            self.output_settings.palette = None;
+                        } else if let Some(palette) = parse_palette(&self.palette_text) {
+                            self.output_settings.palette = Some(palette);
+                        }
+                        self.check_output_levels();
+                    }
+                });
+
+                if let Some((levels, representable)) = self.lost_levels {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("⚠ Render has {levels} levels, the PNG can only hold {representable}.")
+                    );
+                }
+            }
+
+            if self.render.is_some() {
+                ui.horizontal(|ui| {
+                    ComboBox::from_id_salt("shader-language")
+                        .selected_text(self.shader_language.to_string())
+                        .show_ui(ui, |ui| {
+                            for l in [ShaderLanguage::Glsl, ShaderLanguage::Wgsl, ShaderLanguage::Godot] {
+                                ui.selectable_value(&mut self.shader_language, l, l.to_string());
+                            }
+                        });
+                    if ui.button("Export Shader").clicked() {
+                        self.export_shader();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Threshold");
+                    ui.add(DragValue::new(&mut self.render_settings.threshold).range(1..=255));
+                    if ui.button("Export BDF").clicked() {
+                        self.export_bdf();
+                    }
+                    if ui.button("Export PSF2").clicked() {
+                        self.export_psf2();
+                    }
+                    if ui.button("Export MonoFont").clicked() {
+                        self.export_mono_font();
+                    }
+                    if ui.button("Export GFX").clicked() {
+                        self.export_gfx_font();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ComboBox::from_id_salt("c-bit-depth")
+                        .selected_text(self.c_bit_depth.to_string())
+                        .show_ui(ui, |ui| {
+                            for d in [BitDepth::One, BitDepth::Two, BitDepth::Four, BitDepth::Eight] {
+                                ui.selectable_value(&mut self.c_bit_depth, d, d.to_string());
+                            }
+                        });
+                    ComboBox::from_id_salt("c-packing")
+                        .selected_text(self.c_packing.to_string())
+                        .show_ui(ui, |ui| {
+                            for p in [PackingOrder::RowMajor, PackingOrder::ColumnMajor] {
+                                ui.selectable_value(&mut self.c_packing, p, p.to_string());
+                            }
+                        });
+                    if ui.button("Export C Header").clicked() {
+                        self.export_c_header();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Channels:");
+                    for (channel, name) in CHANNEL_NAMES.iter().enumerate() {
+                        let text = if self.channel_pack[channel].is_some() {
+                            format!("{name} ✔")
+                        } else {
+                            name.to_string()
+                        };
+                        let resp = ui.button(text)
+                            .on_hover_text("Click to assign the current render, right click to clear");
+                        if resp.clicked() {
+                            self.assign_channel(channel);
+                        } else if resp.secondary_clicked() {
+                            self.channel_pack[channel] = None;
+                        }
+                    }
+                    if self.channel_pack.iter().any(|c| c.is_some()) && ui.button("Export Packed").clicked() {
+                        self.export_channel_pack();
+                    }
+                });
+            }
+
         });
     }
 
@@ -133,6 +524,19 @@ impl FontRasterizerApp {
             }
         });
 
+        // Missing Glyphs
+        if self.render_settings.input.is_some() {
+            ComboBox::from_label("Missing Glyphs")
+                .selected_text(self.render_settings.missing_glyphs.to_string())
+                .show_ui(ui, |ui| {
+                    for p in [MissingGlyphPolicy::Skip, MissingGlyphPolicy::Notdef, MissingGlyphPolicy::EmptyCell] {
+                        if ui.selectable_value(&mut self.render_settings.missing_glyphs, p, p.to_string()).changed() {
+                            self.render_font();
+                        };
+                    }
+                });
+        }
+
         ui.horizontal(|ui| {
             ui.label("Render Height");
             let resp = ui.add(DragValue::new(&mut self.render_settings.render_height).range(1..=1000).speed(0.1));
@@ -302,6 +706,178 @@ impl FontRasterizerApp {
         if self.render_settings.dedup_property.is_none() {
             if ui.checkbox(&mut self.render_settings.dedup_exact_duplicate, "Remove only exact duplicates").changed() {self.render_font();};
         }
+
+        // Supersampling
+        ui.horizontal(|ui| {
+            if ui.add(DragValue::new(&mut self.render_settings.supersampling).range(1..=8).prefix("Supersampling: ").suffix("×")).changed() {
+                self.render_font();
+            }
+            if self.render_settings.supersampling > 1 {
+                ComboBox::from_id_salt("downsample-filter")
+                    .selected_text(self.render_settings.downsample_filter.to_string())
+                    .show_ui(ui, |ui| {
+                        for f in [DownsampleFilter::Box, DownsampleFilter::Lanczos] {
+                            if ui.selectable_value(&mut self.render_settings.downsample_filter, f, f.to_string()).changed() {
+                                self.render_font();
+                            };
+                        }
+                    });
+            }
+        });
+
+        // Synthetic Styles
+        ui.horizontal(|ui| {
+            if ui.add(DragValue::new(&mut self.render_settings.embolden).speed(0.05).range(0.0..=4.0).prefix("Bold: ").suffix(" px")).changed() {
+                self.render_font();
+            }
+            if ui.add(DragValue::new(&mut self.render_settings.oblique_angle).speed(0.5).range(-45.0..=45.0).prefix("Oblique: ").suffix("°")).changed() {
+                self.render_font();
+            }
+        });
+
+        // Coverage Transfer
+        ComboBox::from_label("Coverage Transfer")
+            .selected_text(self.render_settings.coverage_transfer.to_string())
+            .show_ui(ui, |ui| {
+                let mut transfers = vec![
+                    CoverageTransfer::Linear,
+                    CoverageTransfer::Gamma(2.2),
+                    CoverageTransfer::Contrast(2.0),
+                    CoverageTransfer::LinearToSrgb,
+                ];
+                // Keep the current parameter when re-selecting the same curve.
+                for t in transfers.iter_mut() {
+                    if std::mem::discriminant(t) == std::mem::discriminant(&self.render_settings.coverage_transfer) {
+                        *t = self.render_settings.coverage_transfer;
+                    }
+                }
+                for t in transfers {
+                    if ui.selectable_value(
+                        &mut self.render_settings.coverage_transfer,
+                        t,
+                        t.to_string()
+                    ).changed() {
+                        self.render_font();
+                    };
+                }
+            });
+
+        match &mut self.render_settings.coverage_transfer {
+            CoverageTransfer::Gamma(value) | CoverageTransfer::Contrast(value) => {
+                if ui.add(DragValue::new(value).speed(0.05).range(0.1..=10.0).prefix("Parameter: ")).changed() {
+                    self.render_font();
+                }
+            },
+            CoverageTransfer::Linear | CoverageTransfer::LinearToSrgb => (),
+        }
+
+        // Effects
+        ComboBox::from_label("Effect")
+            .selected_text(self.render_settings.effects.effect.to_string())
+            .show_ui(ui, |ui| {
+                for e in [GlyphEffect::None, GlyphEffect::Outline, GlyphEffect::Glow] {
+                    if ui.selectable_value(&mut self.render_settings.effects.effect, e, e.to_string()).changed() {
+                        self.render_font();
+                    };
+                }
+            });
+
+        if self.render_settings.effects.effect != GlyphEffect::None
+            && ui.add(DragValue::new(&mut self.render_settings.effects.radius).range(1..=16).prefix("Radius: ").suffix(" px")).changed()
+        {
+            self.render_font();
+        }
+
+        let mut drop_shadow = self.render_settings.effects.shadow.is_some();
+        if ui.checkbox(&mut drop_shadow, "Drop shadow").changed() {
+            self.render_settings.effects.shadow = drop_shadow.then(DropShadow::default);
+            self.render_font();
+        }
+        if let Some(shadow) = &mut self.render_settings.effects.shadow {
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                changed |= ui.add(DragValue::new(&mut shadow.offset.0).range(-16..=16).prefix("x: ")).changed();
+                changed |= ui.add(DragValue::new(&mut shadow.offset.1).range(-16..=16).prefix("y: ")).changed();
+                changed |= ui.add(DragValue::new(&mut shadow.intensity).prefix("Intensity: ")).changed();
+            });
+            if changed {
+                self.render_font();
+            }
+        }
+
+        if self.render_settings.effects.is_active()
+            && ui.checkbox(&mut self.render_settings.effects.separate_channel, "Effects in green channel").changed()
+        {
+            self.render_font();
+        }
+
+        // Dithering
+        ComboBox::from_label("Dithering")
+            .selected_text(self.render_settings.dithering.to_string())
+            .show_ui(ui, |ui| {
+                for d in [Dithering::None, Dithering::Bayer, Dithering::FloydSteinberg, Dithering::Atkinson] {
+                    if ui.selectable_value(&mut self.render_settings.dithering, d, d.to_string()).changed() {
+                        self.render_font();
+                    };
+                }
+            });
+
+        // Transform
+        ComboBox::from_label("Rotation")
+            .selected_text(self.render_settings.transform.rotation.to_string())
+            .show_ui(ui, |ui| {
+                for r in [Rotation::None, Rotation::Clockwise, Rotation::HalfTurn, Rotation::CounterClockwise] {
+                    if ui.selectable_value(&mut self.render_settings.transform.rotation, r, r.to_string()).changed() {
+                        self.render_font();
+                    };
+                }
+            });
+        ui.horizontal(|ui| {
+            let mut changed = ui.checkbox(&mut self.render_settings.transform.mirror_horizontal, "Mirror horizontally").changed();
+            changed |= ui.checkbox(&mut self.render_settings.transform.mirror_vertical, "Mirror vertically").changed();
+            changed |= ui.checkbox(&mut self.render_settings.transform.invert, "Invert").changed();
+            if changed {
+                self.render_font();
+            }
+        });
+
+        // Color Mode
+        ComboBox::from_label("Color Mode")
+            .selected_text(self.render_settings.color_mode.to_string())
+            .show_ui(ui, |ui| {
+                let modes = if let ColorMode::Rgba { .. } = self.render_settings.color_mode {
+                    vec![ColorMode::Grayscale, self.render_settings.color_mode]
+                } else {
+                    vec![ColorMode::Grayscale, ColorMode::RGBA]
+                };
+                for m in modes {
+                    if ui.selectable_value(
+                        &mut self.render_settings.color_mode,
+                        m,
+                        m.to_string()
+                    ).changed() {
+                        self.render_font();
+                    };
+                }
+            });
+
+        if let ColorMode::Rgba { mut foreground, mut background, mut premultiplied } = self.render_settings.color_mode {
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                changed |= ui.color_edit_button_srgba_unmultiplied(&mut foreground).changed();
+                ui.label("Foreground");
+            });
+            ui.horizontal(|ui| {
+                changed |= ui.color_edit_button_srgba_unmultiplied(&mut background).changed();
+                ui.label("Background");
+            });
+            changed |= ui.checkbox(&mut premultiplied, "Premultiplied alpha").changed();
+
+            if changed {
+                self.render_settings.color_mode = ColorMode::Rgba { foreground, background, premultiplied };
+                self.render_font();
+            }
+        }
     }
 
 }
@@ -337,9 +913,11 @@ impl eframe::App for FontRasterizerApp {
                     let cell_filled = self.render_info.cell_filled();
                     let cell_count = cell_h_count * cell_v_count;
                     let empty_cells = cell_count - cell_filled;
+                    let fallback_glyphs = self.rasterizations.iter().filter(|cr| cr.get_source() != 0).count();
                     let info_text = format!(
-                        "{} characters rendered | Cell size: {}x{} pixels | Cell padding: L: {} R: {} U: {} D: {} | Cell count: {}x{} ({}) | Empty cells: {} | Texture size: {}x{} pixels", 
+                        "{} characters rendered ({} from fallbacks) | Cell size: {}x{} pixels | Cell padding: L: {} R: {} U: {} D: {} | Cell count: {}x{} ({}) | Empty cells: {} | Texture size: {}x{} pixels", 
                         cell_filled, 
+                        fallback_glyphs,
                         cell_width, 
                         cell_height,
                         left,
@@ -353,7 +931,14 @@ impl eframe::App for FontRasterizerApp {
                         texture_width,
                         texture_height
                     );
-                    ui.label(info_text)
+                    ui.label(info_text);
+
+                    let missing_chars = self.render_info.missing_chars();
+                    if !missing_chars.is_empty() {
+                        ui.separator();
+                        let listed = missing_chars.iter().map(|c| format!("{c:?}")).collect::<Vec<_>>().join(" ");
+                        ui.colored_label(ui.visuals().warn_fg_color, format!("Missing {}: {listed}", missing_chars.len()));
+                    }
                 });
             });
 
@@ -409,7 +994,7 @@ impl Display for AppError {
 
 pub fn get_font_face() -> Result<FontFace, AppError> {
     let font_path = rfd::FileDialog::new()
-        .add_filter("font", &["ttf", "ttc", "otf"])
+        .add_filter("font", &["ttf", "ttc", "otf", "otc"])
         .set_directory("/")
         .pick_file()
         .ok_or(AppError::NoFontPath)?;
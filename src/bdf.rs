@@ -0,0 +1,122 @@
+use std::{fmt::Write, fs};
+
+use fontdue::LineMetrics;
+
+use crate::{
+    font_face::FontMetadata,
+    rasterization::Rasterizations,
+    renderer::{pack_bits, pick_save_path, RendererError},
+};
+
+/// Writes thresholded rasterizations as a BDF 2.1 bitmap font.
+///
+/// Each glyph keeps its own bounding box from fontdue `Metrics`, so the output
+/// is a proportional font even though the atlas uses fixed cells. The source font's
+/// copyright and license carry over as the `COPYRIGHT` and `NOTICE` properties.
+pub fn generate_bdf(
+    family: &str,
+    metadata: &FontMetadata,
+    h_line_metrics: Option<LineMetrics>,
+    rasterizations: &Rasterizations,
+    pixel_height: f32,
+    threshold: u8,
+) -> String {
+    let pixel_size = pixel_height.round() as i32;
+
+    // Union of every glyph bounding box, relative to the origin.
+    let (mut xmin, mut ymin, mut xmax, mut ymax) = (0, 0, 0, 0);
+    for raster in rasterizations {
+        let metrics = raster.get_metrics();
+        xmin = xmin.min(metrics.xmin);
+        ymin = ymin.min(metrics.ymin);
+        xmax = xmax.max(metrics.xmin + metrics.width as i32);
+        ymax = ymax.max(metrics.ymin + metrics.height as i32);
+    }
+
+    let (ascent, descent) = if let Some(l_m) = h_line_metrics {
+        (l_m.ascent.ceil() as i32, (-l_m.descent).ceil() as i32)
+    } else {
+        (ymax, -ymin)
+    };
+
+    let average_width = if rasterizations.is_empty() {
+        0
+    } else {
+        rasterizations
+            .iter()
+            .map(|cr| cr.get_metrics().advance_width.round() as i32)
+            .sum::<i32>()
+            * 10
+            / rasterizations.len() as i32
+    };
+
+    let mut bdf = String::new();
+    let _ = writeln!(bdf, "STARTFONT 2.1");
+    let _ = writeln!(
+        bdf,
+        "FONT -FontRasterizer-{}-Medium-R-Normal--{pixel_size}-{}-72-72-P-{average_width}-ISO10646-1",
+        family.replace('-', " "),
+        pixel_size * 10
+    );
+    let _ = writeln!(bdf, "SIZE {pixel_size} 72 72");
+    let _ = writeln!(bdf, "FONTBOUNDINGBOX {} {} {xmin} {ymin}", xmax - xmin, ymax - ymin);
+    // String properties are quoted, with quotes inside doubled.
+    let strings = [
+        ("FAMILY_NAME", &metadata.family),
+        ("FONT_VERSION", &metadata.version),
+        ("COPYRIGHT", &metadata.copyright),
+        ("NOTICE", &metadata.license),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| {
+        value.as_ref().map(|v| format!("{name} \"{}\"", v.replace('"', "\"\"").replace(['\r', '\n'], " ")))
+    })
+    .collect::<Vec<_>>();
+
+    let _ = writeln!(bdf, "STARTPROPERTIES {}", 2 + strings.len());
+    let _ = writeln!(bdf, "FONT_ASCENT {ascent}");
+    let _ = writeln!(bdf, "FONT_DESCENT {descent}");
+    for property in strings {
+        let _ = writeln!(bdf, "{property}");
+    }
+    let _ = writeln!(bdf, "ENDPROPERTIES");
+    let _ = writeln!(bdf, "CHARS {}", rasterizations.len());
+
+    for raster in rasterizations {
+        let metrics = raster.get_metrics();
+        let advance = metrics.advance_width.round() as i32;
+        let bitmap = pack_bits(&raster.get_bitmap(threshold), metrics.width);
+
+        let _ = writeln!(bdf, "STARTCHAR U+{:04X}", raster.get_char() as u32);
+        let _ = writeln!(bdf, "ENCODING {}", raster.get_char() as u32);
+        let _ = writeln!(bdf, "SWIDTH {} 0", advance * 1000 / pixel_size.max(1));
+        let _ = writeln!(bdf, "DWIDTH {advance} 0");
+        let _ = writeln!(bdf, "BBX {} {} {} {}", metrics.width, metrics.height, metrics.xmin, metrics.ymin);
+        let _ = writeln!(bdf, "BITMAP");
+        // Rows are padded to whole bytes, most significant bit first.
+        for row in bitmap.chunks(metrics.width.div_ceil(8).max(1)) {
+            for byte in row {
+                let _ = write!(bdf, "{byte:02X}");
+            }
+            let _ = writeln!(bdf);
+        }
+        let _ = writeln!(bdf, "ENDCHAR");
+    }
+
+    let _ = writeln!(bdf, "ENDFONT");
+    bdf
+}
+
+pub fn write_bdf(
+    family: &str,
+    metadata: &FontMetadata,
+    h_line_metrics: Option<LineMetrics>,
+    rasterizations: &Rasterizations,
+    pixel_height: f32,
+    threshold: u8,
+) -> Result<(), RendererError> {
+    let bdf_path = pick_save_path(family.into(), "bdf", "bdf")?;
+
+    fs::write(bdf_path, generate_bdf(family, metadata, h_line_metrics, rasterizations, pixel_height, threshold))
+        .map_err(|_| RendererError::CreationError)
+}
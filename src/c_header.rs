@@ -0,0 +1,113 @@
+use std::{fmt::{Display, Write}, fs};
+
+use crate::{font_face::FontMetadata, renderer::{const_identifier, pack_rows, pick_save_path, BitDepth, RenderData, RenderInfo, RendererError}};
+
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum PackingOrder {
+    #[default]
+    RowMajor,
+    ColumnMajor,
+}
+
+impl Display for PackingOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RowMajor => write!(f, "Row-major"),
+            Self::ColumnMajor => write!(f, "Column-major"),
+        }
+    }
+}
+
+/// Packs the atlas at `bit_depth`, most significant bits first.
+///
+/// Row-major packing walks rows left to right, column-major packing walks columns top to
+/// bottom. Either way every row (or column) starts on a byte boundary. At 1 bpp pixels at or
+/// above `threshold` are set, like in the other 1-bit exports.
+pub fn pack_atlas(render_data: &RenderData, bit_depth: BitDepth, packing: PackingOrder, threshold: u8) -> Vec<u8> {
+    let (width, height) = (render_data.width(), render_data.height());
+    let (lines, line_length) = match packing {
+        PackingOrder::RowMajor => (height, width),
+        PackingOrder::ColumnMajor => (width, height),
+    };
+
+    let levels = (0..lines)
+        .flat_map(|line| (0..line_length).map(move |i| match packing {
+            PackingOrder::RowMajor => (i, line),
+            PackingOrder::ColumnMajor => (line, i),
+        }))
+        .map(|(x, y)| bit_depth.quantize_at(render_data.pixels()[x + y * width], threshold))
+        .collect::<Vec<_>>();
+    pack_rows(&levels, line_length, bit_depth)
+}
+
+pub fn generate_c_header(stem: &str, metadata: &FontMetadata, render_data: &RenderData, render_info: &RenderInfo, bit_depth: BitDepth, packing: PackingOrder, threshold: u8) -> String {
+    let name = const_identifier(stem);
+    let prefix = name.to_lowercase();
+    let (cell_width, cell_height) = render_info.cell_size();
+    let (cell_h_count, cell_v_count) = render_info.cell_count();
+    let (left, right, up, down) = render_info.cell_padding();
+    let bitmap = pack_atlas(render_data, bit_depth, packing, threshold);
+
+    // Sorted by codepoint so the table can be binary searched.
+    let mut glyphs = render_info
+        .cell_glyphs()
+        .iter()
+        .enumerate()
+        .map(|(idx, (c, _))| (*c as u32, idx, render_info.cell_origin(idx)))
+        .collect::<Vec<_>>();
+    glyphs.sort_by_key(|(codepoint, _, _)| *codepoint);
+
+    let mut header = String::new();
+    let _ = writeln!(header, "// Generated by Font Rasterizer from {stem}.");
+    header.push_str(&metadata.comment_block("//"));
+    let _ = writeln!(header, "#ifndef {name}_H");
+    let _ = writeln!(header, "#define {name}_H\n");
+    let _ = writeln!(header, "#include <stdint.h>\n");
+    let _ = writeln!(header, "#define {name}_WIDTH {}", render_data.width());
+    let _ = writeln!(header, "#define {name}_HEIGHT {}", render_data.height());
+    let _ = writeln!(header, "#define {name}_BPP {}", bit_depth.bits());
+    let _ = writeln!(header, "#define {name}_COLUMN_MAJOR {}", (packing == PackingOrder::ColumnMajor) as u8);
+    let _ = writeln!(header, "#define {name}_CELL_WIDTH {cell_width}");
+    let _ = writeln!(header, "#define {name}_CELL_HEIGHT {cell_height}");
+    let _ = writeln!(header, "#define {name}_CELL_COLUMNS {cell_h_count}");
+    let _ = writeln!(header, "#define {name}_CELL_ROWS {cell_v_count}");
+    let _ = writeln!(header, "#define {name}_PADDING_LEFT {left}");
+    let _ = writeln!(header, "#define {name}_PADDING_RIGHT {right}");
+    let _ = writeln!(header, "#define {name}_PADDING_UP {up}");
+    let _ = writeln!(header, "#define {name}_PADDING_DOWN {down}");
+    let _ = writeln!(header, "#define {name}_BASELINE {}", render_info.baseline());
+    let _ = writeln!(header, "#define {name}_GLYPH_COUNT {}\n", glyphs.len());
+
+    let _ = writeln!(header, "static const uint8_t {prefix}_bitmap[{}] = {{", bitmap.len());
+    for line in bitmap.chunks(16) {
+        let bytes = line.iter().map(|b| format!("0x{b:02x}")).collect::<Vec<_>>().join(", ");
+        let _ = writeln!(header, "    {bytes},");
+    }
+    let _ = writeln!(header, "}};\n");
+
+    let _ = writeln!(header, "typedef struct {{");
+    let _ = writeln!(header, "    uint32_t codepoint;");
+    let _ = writeln!(header, "    uint16_t cell;");
+    let _ = writeln!(header, "    uint16_t x;");
+    let _ = writeln!(header, "    uint16_t y;");
+    let _ = writeln!(header, "}} {prefix}_glyph_t;\n");
+
+    let _ = writeln!(header, "static const {prefix}_glyph_t {prefix}_glyphs[{}] = {{", glyphs.len().max(1));
+    for (codepoint, idx, (x, y)) in &glyphs {
+        let _ = writeln!(header, "    {{0x{codepoint:04X}, {idx}, {x}, {y}}},");
+    }
+    if glyphs.is_empty() {
+        let _ = writeln!(header, "    {{0, 0, 0, 0}},");
+    }
+    let _ = writeln!(header, "}};\n");
+    let _ = writeln!(header, "#endif // {name}_H");
+
+    header
+}
+
+pub fn write_c_header(stem: &str, metadata: &FontMetadata, render_data: &RenderData, render_info: &RenderInfo, bit_depth: BitDepth, packing: PackingOrder, threshold: u8) -> Result<(), RendererError> {
+    let header_path = pick_save_path(const_identifier(stem).to_lowercase(), "C header", "h")?;
+
+    fs::write(header_path, generate_c_header(stem, metadata, render_data, render_info, bit_depth, packing, threshold))
+        .map_err(|_| RendererError::CreationError)
+}
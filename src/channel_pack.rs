@@ -0,0 +1,146 @@
+use std::{fmt::Write, fs};
+
+use crate::{font_face::FontMetadata, renderer::{write_image, OutputImage, OutputSettings, PixelEncoding, PixelFormat, RenderData, RenderInfo, RendererError}};
+
+pub const CHANNEL_NAMES: [&str; 4] = ["R", "G", "B", "A"];
+
+/// A finished render assigned to one channel of a packed texture.
+#[derive(Clone)]
+pub struct PackedChannel {
+    pub font_name: String,
+    pub font_metadata: FontMetadata,
+    pub render_height: f32,
+    pub input: Option<String>,
+    pub render_data: RenderData,
+    pub render_info: RenderInfo,
+}
+
+impl PackedChannel {
+    fn padded_cell_size(&self) -> (usize, usize) {
+        let (cell_width, cell_height) = self.render_info.cell_size();
+        let (left, right, up, down) = self.render_info.cell_padding();
+        (cell_width + left + right, cell_height + up + down)
+    }
+}
+
+/// Cell grid shared by every packed channel.
+pub struct PackedGrid {
+    pub cell_size: (usize, usize),
+    pub cell_count: (usize, usize),
+}
+
+/// The shared grid uses the largest padded cell and the largest cell counts of any channel,
+/// so every channel's glyphs keep their index and fit without clipping.
+pub fn packed_grid(channels: &[Option<PackedChannel>; 4]) -> PackedGrid {
+    let mut cell_size = (0, 0);
+    let mut cell_count = (0, 0);
+    for channel in channels.iter().flatten() {
+        let (w, h) = channel.padded_cell_size();
+        let (h_count, v_count) = channel.render_info.cell_count();
+        cell_size = (cell_size.0.max(w), cell_size.1.max(h));
+        cell_count = (cell_count.0.max(h_count), cell_count.1.max(v_count));
+    }
+    PackedGrid { cell_size, cell_count }
+}
+
+/// Writes each assigned channel's cells into its own RGBA channel, centered in the shared cells.
+///
+/// Glyphs are placed in the render direction of the first assigned channel. An unassigned
+/// alpha channel is left opaque so the color channels stay visible. The texture is tagged
+/// with the channels' pixel encoding when they all agree, and as linear otherwise.
+pub fn pack_channels(channels: &[Option<PackedChannel>; 4]) -> OutputImage {
+    let grid = packed_grid(channels);
+    let (cell_width, cell_height) = grid.cell_size;
+    let (cell_h_count, cell_v_count) = grid.cell_count;
+    let width = cell_h_count * cell_width;
+    let height = cell_v_count * cell_height;
+    let direction = channels
+        .iter()
+        .flatten()
+        .next()
+        .map(|c| c.render_info.render_direction())
+        .unwrap_or_default();
+
+    let mut pixels = vec![0u8; width * height * 4];
+    if channels[3].is_none() {
+        pixels.iter_mut().skip(3).step_by(4).for_each(|a| *a = 255);
+    }
+
+    for (channel_idx, channel) in channels.iter().enumerate() {
+        let Some(channel) = channel else {
+            continue;
+        };
+        let (src_width, src_height) = channel.padded_cell_size();
+        let (left, _, up, _) = channel.render_info.cell_padding();
+        let x_offset = (cell_width - src_width) / 2;
+        let y_offset = (cell_height - src_height) / 2;
+
+        for idx in 0..channel.render_info.cell_filled() {
+            let (src_x, src_y) = channel.render_info.cell_origin(idx);
+            let (src_x, src_y) = (src_x - left, src_y - up);
+            let (cell_x, cell_y) = direction.cell_position(idx, cell_h_count, cell_v_count);
+            let dst_x = cell_x * cell_width + x_offset;
+            let dst_y = cell_y * cell_height + y_offset;
+
+            for y in 0..src_height {
+                for x in 0..src_width {
+                    let value = channel.render_data.pixels()[(src_y + y) * channel.render_data.width() + src_x + x];
+                    pixels[((dst_y + y) * width + dst_x + x) * 4 + channel_idx] = value;
+                }
+            }
+        }
+    }
+
+    let mut encodings = channels.iter().flatten().map(|c| c.render_data.encoding());
+    let encoding = match encodings.next() {
+        Some(first) if encodings.all(|e| e == first) => first,
+        _ => PixelEncoding::Linear,
+    };
+
+    OutputImage::new(width, height, PixelFormat::Rgba8, pixels).with_encoding(encoding)
+}
+
+/// Describes which render went into which channel.
+pub fn generate_manifest(texture_name: &str, channels: &[Option<PackedChannel>; 4]) -> String {
+    let grid = packed_grid(channels);
+
+    let mut manifest = String::new();
+    let _ = writeln!(manifest, "# Font Rasterizer channel pack");
+    let _ = writeln!(manifest, "texture = {texture_name}");
+    let _ = writeln!(manifest, "texture_size = {}x{}", grid.cell_count.0 * grid.cell_size.0, grid.cell_count.1 * grid.cell_size.1);
+    let _ = writeln!(manifest, "cell_size = {}x{}", grid.cell_size.0, grid.cell_size.1);
+    let _ = writeln!(manifest, "cell_count = {}x{}", grid.cell_count.0, grid.cell_count.1);
+
+    for (name, channel) in CHANNEL_NAMES.iter().zip(channels) {
+        let _ = writeln!(manifest, "\n[{name}]");
+        let Some(channel) = channel else {
+            let _ = writeln!(manifest, "unused = true");
+            continue;
+        };
+        let (cell_width, cell_height) = channel.render_info.cell_size();
+        let (left, right, up, down) = channel.render_info.cell_padding();
+        let characters = channel.render_info.cell_glyphs().iter().map(|(c, _)| *c).collect::<String>();
+        let _ = writeln!(manifest, "font = {}", channel.font_name);
+        for (label, value) in channel.font_metadata.entries() {
+            let _ = writeln!(manifest, "font_{} = {value:?}", label.to_lowercase().replace(' ', "_"));
+        }
+        let _ = writeln!(manifest, "render_height = {}", channel.render_height);
+        let _ = writeln!(manifest, "input = {}", channel.input.as_deref().map(|i| format!("{i:?}")).unwrap_or("all".into()));
+        let _ = writeln!(manifest, "cell_size = {cell_width}x{cell_height}");
+        let _ = writeln!(manifest, "cell_padding = {left} {right} {up} {down}");
+        let _ = writeln!(manifest, "baseline = {}", channel.render_info.baseline());
+        let _ = writeln!(manifest, "glyph_count = {}", channel.render_info.cell_filled());
+        let _ = writeln!(manifest, "characters = {characters:?}");
+    }
+
+    manifest
+}
+
+/// Writes the packed texture, then its manifest next to it as a `.txt` file.
+pub fn write_channel_pack(name: String, channels: &[Option<PackedChannel>; 4], output_settings: &OutputSettings, threshold: u8) -> Result<(), RendererError> {
+    let render_path = write_image(name, &pack_channels(channels), output_settings, threshold)?;
+    let texture_name = render_path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+
+    fs::write(render_path.with_extension("txt"), generate_manifest(texture_name, channels))
+        .map_err(|_| RendererError::CreationError)
+}
@@ -0,0 +1,215 @@
+use std::fmt::Display;
+
+use fontdue::Metrics;
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum GlyphEffect {
+    #[default]
+    None,
+    /// Morphological dilation of the coverage by a disc of `radius` pixels.
+    Outline,
+    /// Gaussian blur of the coverage reaching `radius` pixels out.
+    Glow,
+}
+
+impl Display for GlyphEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Outline => write!(f, "Outline"),
+            Self::Glow => write!(f, "Glow"),
+        }
+    }
+}
+
+/// Hard copy of the glyph, and its outline or glow, drawn under it.
+#[derive(Clone, Copy, PartialEq)]
+pub struct DropShadow {
+    /// Offset in pixels, positive values move the shadow right and down.
+    pub offset: (isize, isize),
+    /// Coverage of the shadow where the glyph is fully covered.
+    pub intensity: u8,
+}
+
+impl Default for DropShadow {
+    fn default() -> Self {
+        Self {
+            offset: (1, 1),
+            intensity: 128,
+        }
+    }
+}
+
+impl DropShadow {
+    /// Offset copy of `pixels`, scaled by the intensity.
+    fn apply(&self, pixels: &[u8], width: usize, height: usize) -> Vec<u8> {
+        let (dx, dy) = self.offset;
+        let mut shadow = vec![0u8; pixels.len()];
+        for y in 0..height as isize {
+            for x in 0..width as isize {
+                let (sx, sy) = (x - dx, y - dy);
+                if (0..width as isize).contains(&sx) && (0..height as isize).contains(&sy) {
+                    let value = pixels[sy as usize * width + sx as usize] as usize * self.intensity as usize;
+                    shadow[y as usize * width + x as usize] = (value / 255) as u8;
+                }
+            }
+        }
+        shadow
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct EffectSettings {
+    pub effect: GlyphEffect,
+    pub radius: usize,
+    pub shadow: Option<DropShadow>,
+    /// Keeps effects out of the glyph coverage, outputs then carry them in the green channel.
+    pub separate_channel: bool,
+}
+
+impl Default for EffectSettings {
+    fn default() -> Self {
+        Self {
+            effect: GlyphEffect::None,
+            radius: 1,
+            shadow: None,
+            separate_channel: false,
+        }
+    }
+}
+
+impl EffectSettings {
+    pub fn is_active(&self) -> bool {
+        self.effect != GlyphEffect::None || self.shadow.is_some()
+    }
+
+    /// Pixels every glyph bitmap, and so every cell, grows by on each side as (left, right, up, down).
+    ///
+    /// The shadow only grows the sides it is offset towards, on top of the outline or glow radius.
+    pub fn extent(&self) -> (usize, usize, usize, usize) {
+        let radius = match self.effect {
+            GlyphEffect::None => 0,
+            GlyphEffect::Outline | GlyphEffect::Glow => self.radius,
+        };
+        let (dx, dy) = self.shadow.map(|s| s.offset).unwrap_or_default();
+        (
+            radius + (-dx).max(0) as usize,
+            radius + dx.max(0) as usize,
+            radius + (-dy).max(0) as usize,
+            radius + dy.max(0) as usize,
+        )
+    }
+
+    /// Whether renders carry a separate effect channel.
+    pub fn writes_channel(&self) -> bool {
+        self.separate_channel && self.is_active()
+    }
+
+    /// Computes the effect coverage for a glyph bitmap that was already grown by `extent`.
+    ///
+    /// The shadow is cast by the glyph together with its outline or glow and ends up under both.
+    pub fn apply(&self, pixels: &[u8], width: usize, height: usize) -> Vec<u8> {
+        let effect = match self.effect {
+            GlyphEffect::None => vec![0; pixels.len()],
+            GlyphEffect::Outline => dilate(pixels, width, height, self.radius),
+            GlyphEffect::Glow => gaussian_blur(pixels, width, height, self.radius),
+        };
+        let Some(shadow) = self.shadow else {
+            return effect;
+        };
+
+        let caster = pixels.iter().zip(&effect).map(|(v, e)| *v.max(e)).collect::<Vec<_>>();
+        let shadow = shadow.apply(&caster, width, height);
+        effect.iter().zip(shadow).map(|(e, s)| composite_over(*e, s)).collect()
+    }
+}
+
+/// Adds empty rows and columns around a glyph bitmap and moves its metrics to match.
+/// The advance width is left alone, the glyph itself doesn't move.
+pub fn grow(metrics: Metrics, pixels: &[u8], (left, right, up, down): (usize, usize, usize, usize)) -> (Metrics, Vec<u8>) {
+    let width = metrics.width + left + right;
+    let height = metrics.height + up + down;
+
+    let mut grown = vec![0u8; width * height];
+    for (y, row) in pixels.chunks(metrics.width.max(1)).enumerate() {
+        let start = (y + up) * width + left;
+        grown[start..start + row.len()].copy_from_slice(row);
+    }
+
+    let mut metrics = metrics;
+    metrics.xmin -= left as i32;
+    metrics.ymin -= down as i32;
+    metrics.width = width;
+    metrics.height = height;
+    metrics.bounds.xmin -= left as f32;
+    metrics.bounds.ymin -= down as f32;
+    metrics.bounds.width += (left + right) as f32;
+    metrics.bounds.height += (up + down) as f32;
+    (metrics, grown)
+}
+
+/// Coverage of `top` laid over `bottom`.
+pub fn composite_over(top: u8, bottom: u8) -> u8 {
+    (top as usize + bottom as usize * (255 - top as usize) / 255) as u8
+}
+
+/// Maximum coverage within a disc of `radius` around each pixel.
+fn dilate(pixels: &[u8], width: usize, height: usize, radius: usize) -> Vec<u8> {
+    let r = radius as isize;
+    let offsets = (-r..=r)
+        .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+        .filter(|(dx, dy)| dx * dx + dy * dy <= r * r)
+        .collect::<Vec<_>>();
+
+    let mut dilated = vec![0u8; pixels.len()];
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            dilated[y as usize * width + x as usize] = offsets
+                .iter()
+                .map(|(dx, dy)| (x + dx, y + dy))
+                .filter(|(sx, sy)| (0..width as isize).contains(sx) && (0..height as isize).contains(sy))
+                .map(|(sx, sy)| pixels[sy as usize * width + sx as usize])
+                .max()
+                .unwrap_or(0);
+        }
+    }
+    dilated
+}
+
+/// Separable Gaussian blur with the kernel cut off at `radius`, three standard deviations out.
+fn gaussian_blur(pixels: &[u8], width: usize, height: usize, radius: usize) -> Vec<u8> {
+    let sigma = (radius as f32 / 3.0).max(0.5);
+    let r = radius as isize;
+    let kernel = (-r..=r)
+        .map(|d| (-((d * d) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect::<Vec<_>>();
+    let total = kernel.iter().sum::<f32>();
+
+    let blur = |source: &[f32], horizontal: bool| -> Vec<f32> {
+        let mut blurred = vec![0.0; source.len()];
+        for y in 0..height as isize {
+            for x in 0..width as isize {
+                let sum = kernel
+                    .iter()
+                    .zip(-r..=r)
+                    .map(|(weight, d)| {
+                        let (sx, sy) = if horizontal { (x + d, y) } else { (x, y + d) };
+                        if (0..width as isize).contains(&sx) && (0..height as isize).contains(&sy) {
+                            weight * source[sy as usize * width + sx as usize]
+                        } else {
+                            0.0
+                        }
+                    })
+                    .sum::<f32>();
+                blurred[y as usize * width + x as usize] = sum / total;
+            }
+        }
+        blurred
+    };
+
+    let source = pixels.iter().map(|v| *v as f32).collect::<Vec<_>>();
+    blur(&blur(&source, true), false)
+        .into_iter()
+        .map(|v| v.round().clamp(0.0, 255.0) as u8)
+        .collect()
+}
@@ -0,0 +1,220 @@
+//! Encoders for the uncompressed texture containers `write_image` can produce besides PNG.
+//!
+//! Images are tagged as sRGB where the container can say so and the pixels went through the
+//! sRGB transfer, sRGB RGBA keeping its alpha linear. Everything else, like coverage and channel
+//! packs, is tagged as linear data so GPUs sample it unchanged.
+
+use crate::renderer::{OutputImage, PixelEncoding, PixelFormat, RendererError};
+
+/// Truevision TGA, uncompressed, stored top to bottom.
+pub fn encode_tga(image: &OutputImage) -> Result<Vec<u8>, RendererError> {
+    let width = u16::try_from(image.width).map_err(|_| RendererError::TooLarge)?;
+    let height = u16::try_from(image.height).map_err(|_| RendererError::TooLarge)?;
+    let (image_type, bits_per_pixel, descriptor) = match image.format {
+        PixelFormat::Gray8 => (3u8, 8u8, 0x20u8),
+        // 8 alpha bits, top left origin.
+        PixelFormat::Rgba8 => (2u8, 32u8, 0x28u8),
+    };
+
+    let mut tga = vec![0u8, 0, image_type, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    tga.extend_from_slice(&width.to_le_bytes());
+    tga.extend_from_slice(&height.to_le_bytes());
+    tga.push(bits_per_pixel);
+    tga.push(descriptor);
+
+    match image.format {
+        PixelFormat::Gray8 => tga.extend_from_slice(&image.pixels),
+        PixelFormat::Rgba8 => {
+            for rgba in image.pixels.chunks_exact(4) {
+                tga.extend_from_slice(&[rgba[2], rgba[1], rgba[0], rgba[3]]);
+            }
+        }
+    }
+    Ok(tga)
+}
+
+/// DirectDraw Surface with a DX10 header, as `R8_UNORM`, `R8G8B8A8_UNORM` or `R8G8B8A8_UNORM_SRGB`.
+pub fn encode_dds(image: &OutputImage) -> Result<Vec<u8>, RendererError> {
+    const DDSD_CAPS: u32 = 0x1;
+    const DDSD_HEIGHT: u32 = 0x2;
+    const DDSD_WIDTH: u32 = 0x4;
+    const DDSD_PITCH: u32 = 0x8;
+    const DDSD_PIXELFORMAT: u32 = 0x1000;
+    const DDPF_FOURCC: u32 = 0x4;
+    const DDSCAPS_TEXTURE: u32 = 0x1000;
+    const DXGI_FORMAT_R8_UNORM: u32 = 61;
+    const DXGI_FORMAT_R8G8B8A8_UNORM: u32 = 28;
+    const DXGI_FORMAT_R8G8B8A8_UNORM_SRGB: u32 = 29;
+    const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+
+    let width = u32::try_from(image.width).map_err(|_| RendererError::TooLarge)?;
+    let height = u32::try_from(image.height).map_err(|_| RendererError::TooLarge)?;
+    let (dxgi_format, channels) = match image.format {
+        PixelFormat::Gray8 => (DXGI_FORMAT_R8_UNORM, 1),
+        PixelFormat::Rgba8 if image.encoding == PixelEncoding::Srgb => (DXGI_FORMAT_R8G8B8A8_UNORM_SRGB, 4),
+        PixelFormat::Rgba8 => (DXGI_FORMAT_R8G8B8A8_UNORM, 4),
+    };
+
+    let mut header = vec![
+        124,
+        DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PITCH | DDSD_PIXELFORMAT,
+        height,
+        width,
+        width * channels,
+        0,
+        0,
+    ];
+    header.extend([0; 11]);
+    // DDS_PIXELFORMAT, the actual format lives in the DX10 header.
+    header.extend([32, DDPF_FOURCC, u32::from_le_bytes(*b"DX10"), 0, 0, 0, 0, 0]);
+    header.extend([DDSCAPS_TEXTURE, 0, 0, 0, 0]);
+    // DDS_HEADER_DXT10
+    header.extend([dxgi_format, D3D10_RESOURCE_DIMENSION_TEXTURE2D, 0, 1, 0]);
+
+    let mut dds = b"DDS ".to_vec();
+    for field in header {
+        dds.extend_from_slice(&field.to_le_bytes());
+    }
+    dds.extend_from_slice(&image.pixels);
+    Ok(dds)
+}
+
+/// KTX 2.0 with a single level, as `VK_FORMAT_R8_UNORM`, `VK_FORMAT_R8_SRGB`, `VK_FORMAT_R8G8B8A8_UNORM`
+/// or `VK_FORMAT_R8G8B8A8_SRGB`.
+pub fn encode_ktx2(image: &OutputImage) -> Result<Vec<u8>, RendererError> {
+    const IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+    const VK_FORMAT_R8_UNORM: u32 = 9;
+    const VK_FORMAT_R8_SRGB: u32 = 15;
+    const VK_FORMAT_R8G8B8A8_UNORM: u32 = 37;
+    const VK_FORMAT_R8G8B8A8_SRGB: u32 = 43;
+    const KHR_DF_MODEL_RGBSDA: u8 = 1;
+    const KHR_DF_PRIMARIES_BT709: u8 = 1;
+    const KHR_DF_TRANSFER_LINEAR: u8 = 1;
+    const KHR_DF_TRANSFER_SRGB: u8 = 2;
+    const KHR_DF_SAMPLE_DATATYPE_LINEAR: u8 = 0x10;
+    const KHR_DF_CHANNEL_ALPHA: u8 = 15;
+
+    let width = u32::try_from(image.width).map_err(|_| RendererError::TooLarge)?;
+    let height = u32::try_from(image.height).map_err(|_| RendererError::TooLarge)?;
+    let (vk_format, transfer, channels): (u32, u8, &[u8]) = match image.format {
+        PixelFormat::Gray8 if image.encoding == PixelEncoding::Srgb => (VK_FORMAT_R8_SRGB, KHR_DF_TRANSFER_SRGB, &[0]),
+        PixelFormat::Gray8 => (VK_FORMAT_R8_UNORM, KHR_DF_TRANSFER_LINEAR, &[0]),
+        // Alpha is stored linearly even in sRGB formats.
+        PixelFormat::Rgba8 if image.encoding == PixelEncoding::Srgb => {
+            (VK_FORMAT_R8G8B8A8_SRGB, KHR_DF_TRANSFER_SRGB, &[0, 1, 2, KHR_DF_CHANNEL_ALPHA | KHR_DF_SAMPLE_DATATYPE_LINEAR])
+        },
+        PixelFormat::Rgba8 => (VK_FORMAT_R8G8B8A8_UNORM, KHR_DF_TRANSFER_LINEAR, &[0, 1, 2, KHR_DF_CHANNEL_ALPHA]),
+    };
+
+    // Data format descriptor: total size, then one basic descriptor block.
+    let block_size = 24 + 16 * channels.len() as u32;
+    let mut dfd = vec![];
+    dfd.extend_from_slice(&(4 + block_size).to_le_bytes());
+    dfd.extend_from_slice(&0u32.to_le_bytes()); // Khronos vendor, basic descriptor type.
+    dfd.extend_from_slice(&2u16.to_le_bytes());
+    dfd.extend_from_slice(&(block_size as u16).to_le_bytes());
+    dfd.extend_from_slice(&[KHR_DF_MODEL_RGBSDA, KHR_DF_PRIMARIES_BT709, transfer, 0]);
+    dfd.extend_from_slice(&[0, 0, 0, 0]); // 1x1x1x1 texel block.
+    dfd.extend_from_slice(&[channels.len() as u8, 0, 0, 0, 0, 0, 0, 0]);
+    for (i, channel) in channels.iter().enumerate() {
+        dfd.extend_from_slice(&((i * 8) as u16).to_le_bytes());
+        dfd.push(7); // Bit length minus one.
+        dfd.push(*channel);
+        dfd.extend_from_slice(&[0, 0, 0, 0]);
+        dfd.extend_from_slice(&0u32.to_le_bytes());
+        dfd.extend_from_slice(&255u32.to_le_bytes());
+    }
+
+    let dfd_offset = 12 + 36 + 32 + 24;
+    let level_offset = (dfd_offset + dfd.len()).next_multiple_of(4);
+    let level_length = image.pixels.len() as u64;
+
+    let mut ktx = IDENTIFIER.to_vec();
+    for field in [vk_format, 1, width, height, 0, 0, 1, 1, 0] {
+        ktx.extend_from_slice(&field.to_le_bytes());
+    }
+    for field in [dfd_offset as u32, dfd.len() as u32, 0, 0] {
+        ktx.extend_from_slice(&field.to_le_bytes());
+    }
+    for field in [0u64, 0, level_offset as u64, level_length, level_length] {
+        ktx.extend_from_slice(&field.to_le_bytes());
+    }
+    ktx.extend_from_slice(&dfd);
+    ktx.resize(level_offset, 0);
+    ktx.extend_from_slice(&image.pixels);
+    Ok(ktx)
+}
+
+/// Quite OK Image format. Grayscale is expanded to RGB, only sRGB images use the sRGB colorspace.
+pub fn encode_qoi(image: &OutputImage) -> Result<Vec<u8>, RendererError> {
+    const QOI_OP_INDEX: u8 = 0x00;
+    const QOI_OP_DIFF: u8 = 0x40;
+    const QOI_OP_LUMA: u8 = 0x80;
+    const QOI_OP_RUN: u8 = 0xc0;
+    const QOI_OP_RGB: u8 = 0xfe;
+    const QOI_OP_RGBA: u8 = 0xff;
+
+    let width = u32::try_from(image.width).map_err(|_| RendererError::TooLarge)?;
+    let height = u32::try_from(image.height).map_err(|_| RendererError::TooLarge)?;
+    let colorspace = if image.encoding == PixelEncoding::Srgb { 0 } else { 1 };
+    let (channels, pixels): (u8, Vec<[u8; 4]>) = match image.format {
+        PixelFormat::Gray8 => (3, image.pixels.iter().map(|v| [*v, *v, *v, 255]).collect()),
+        PixelFormat::Rgba8 => (4, image.pixels.chunks_exact(4).map(|p| [p[0], p[1], p[2], p[3]]).collect()),
+    };
+
+    let mut qoi = b"qoif".to_vec();
+    qoi.extend_from_slice(&width.to_be_bytes());
+    qoi.extend_from_slice(&height.to_be_bytes());
+    qoi.push(channels);
+    qoi.push(colorspace);
+
+    let mut index = [[0u8; 4]; 64];
+    let mut previous = [0u8, 0, 0, 255];
+    let mut run = 0u8;
+
+    for (i, pixel) in pixels.iter().enumerate() {
+        if *pixel == previous {
+            run += 1;
+            if run == 62 || i == pixels.len() - 1 {
+                qoi.push(QOI_OP_RUN | (run - 1));
+                run = 0;
+            }
+            continue;
+        }
+
+        if run > 0 {
+            qoi.push(QOI_OP_RUN | (run - 1));
+            run = 0;
+        }
+
+        let [r, g, b, a] = *pixel;
+        let hash = (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64;
+        if index[hash] == *pixel {
+            qoi.push(QOI_OP_INDEX | hash as u8);
+        } else {
+            index[hash] = *pixel;
+            if a == previous[3] {
+                let dr = r.wrapping_sub(previous[0]) as i8;
+                let dg = g.wrapping_sub(previous[1]) as i8;
+                let db = b.wrapping_sub(previous[2]) as i8;
+                let dr_dg = dr.wrapping_sub(dg);
+                let db_dg = db.wrapping_sub(dg);
+
+                if (-2..=1).contains(&dr) && (-2..=1).contains(&dg) && (-2..=1).contains(&db) {
+                    qoi.push(QOI_OP_DIFF | ((dr + 2) as u8) << 4 | ((dg + 2) as u8) << 2 | (db + 2) as u8);
+                } else if (-8..=7).contains(&dr_dg) && (-32..=31).contains(&dg) && (-8..=7).contains(&db_dg) {
+                    qoi.push(QOI_OP_LUMA | (dg + 32) as u8);
+                    qoi.push(((dr_dg + 8) as u8) << 4 | (db_dg + 8) as u8);
+                } else {
+                    qoi.extend_from_slice(&[QOI_OP_RGB, r, g, b]);
+                }
+            } else {
+                qoi.extend_from_slice(&[QOI_OP_RGBA, r, g, b, a]);
+            }
+        }
+        previous = *pixel;
+    }
+
+    qoi.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+    Ok(qoi)
+}
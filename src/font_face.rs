@@ -2,12 +2,16 @@ use std::{fmt::Display, fs::File, io::Read, path::PathBuf};
 
 use fontdue::{Font, LineMetrics};
 
-use crate::rasterization::{CharRaster, Rasterizations};
+use crate::{
+    rasterization::{downsample, embolden, oblique, CharRaster, MissingGlyphPolicy, Rasterizations},
+    renderer::RenderSettings,
+};
 
 #[derive(Debug)]
 pub enum FontFaceError {
     FontOpeningError,
     CreationError(&'static str),
+    FaceIndex(u32, usize),
 }
 
 impl Display for FontFaceError {
@@ -15,30 +19,219 @@ impl Display for FontFaceError {
         match self {
             Self::FontOpeningError => write!(f, "Encountered error opening font file."),
             Self::CreationError(err) => write!(f, "Encountered error creating font face: {err}"),
+            Self::FaceIndex(index, count) => write!(f, "Face {index} is out of range, the file holds {count} face(s)."),
         }
     }
 }
 
+/// Names and sizes read from a face's `name`, `head` and `maxp` tables.
+#[derive(Clone, Default)]
+pub struct FontMetadata {
+    pub family: Option<String>,
+    pub subfamily: Option<String>,
+    pub full_name: Option<String>,
+    pub version: Option<String>,
+    pub copyright: Option<String>,
+    pub license: Option<String>,
+    pub license_url: Option<String>,
+    pub units_per_em: u16,
+    pub glyph_count: u16,
+    pub mapped_chars: usize,
+}
+
+impl FontMetadata {
+    fn read(data: &[u8], face_index: u32, font: &Font) -> Self {
+        let face = ttf_parser::Face::parse(data, face_index).ok();
+        let name = |id| face.as_ref().and_then(|face| name_string(face, id));
+        Self {
+            family: name(ttf_parser::name_id::FAMILY),
+            subfamily: name(ttf_parser::name_id::SUBFAMILY),
+            full_name: name(ttf_parser::name_id::FULL_NAME),
+            version: name(ttf_parser::name_id::VERSION),
+            copyright: name(ttf_parser::name_id::COPYRIGHT_NOTICE),
+            license: name(ttf_parser::name_id::LICENSE),
+            license_url: name(ttf_parser::name_id::LICENSE_URL),
+            units_per_em: font.units_per_em() as u16,
+            glyph_count: font.glyph_count(),
+            mapped_chars: font.chars().len(),
+        }
+    }
+
+    /// Labelled values for display and manifests, names the font doesn't have are left out.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let names = [
+            ("Family", &self.family),
+            ("Subfamily", &self.subfamily),
+            ("Full name", &self.full_name),
+            ("Version", &self.version),
+            ("Copyright", &self.copyright),
+            ("License", &self.license),
+            ("License URL", &self.license_url),
+        ];
+        let mut entries = names
+            .into_iter()
+            .filter_map(|(label, value)| value.clone().map(|v| (label, v)))
+            .collect::<Vec<_>>();
+        entries.push(("Units per em", self.units_per_em.to_string()));
+        entries.push(("Glyph count", self.glyph_count.to_string()));
+        entries.push(("Mapped characters", self.mapped_chars.to_string()));
+        entries
+    }
+
+    /// Name, version, copyright and license of the font, one line each with multi-line values indented.
+    pub fn notice(&self) -> Vec<String> {
+        let names = [
+            ("Font", self.full_name.as_ref().or(self.family.as_ref())),
+            ("Version", self.version.as_ref()),
+            ("Copyright", self.copyright.as_ref()),
+            ("License", self.license.as_ref()),
+            ("License URL", self.license_url.as_ref()),
+        ];
+        let mut lines = vec![];
+        for (label, value) in names {
+            let Some(value) = value else {
+                continue;
+            };
+            let mut value_lines = value.lines().map(str::trim).filter(|l| !l.is_empty());
+            lines.push(format!("{label}: {}", value_lines.next().unwrap_or_default()));
+            lines.extend(value_lines.map(|l| format!("  {l}")));
+        }
+        lines
+    }
+
+    /// The notice as line comments starting with `prefix`, for the top of generated sources.
+    pub fn comment_block(&self, prefix: &str) -> String {
+        self.notice().iter().map(|line| format!("{prefix} {line}\n")).collect()
+    }
+
+    /// Font details kept next to an export named `export_name`, in the channel pack manifest format.
+    pub fn generate_notice_file(&self, export_name: &str) -> String {
+        let mut notice = format!("# Font Rasterizer font notice\nexport = {export_name}\n");
+        for (label, value) in self.entries() {
+            notice.push_str(&format!("font_{} = {value:?}\n", label.to_lowercase().replace(' ', "_")));
+        }
+        notice
+    }
+}
+
 pub struct FontFace {
     font: Font,
     path: PathBuf,
+    face_index: u32,
+    face_names: Vec<String>,
+    metadata: FontMetadata,
+    /// Fonts tried in order for characters this one doesn't map.
+    fallbacks: Vec<FontFace>,
 }
 
 impl FontFace {
     pub fn load(font_path: PathBuf) -> Result<Self, FontFaceError> {
+        Self::load_face(font_path, 0)
+    }
+
+    /// Loads face `face_index` of a font collection (`.ttc`/`.otc`), plain font files only have face 0.
+    pub fn load_face(font_path: PathBuf, face_index: u32) -> Result<Self, FontFaceError> {
         let mut buf = vec![];
         let mut file = File::open(&font_path).map_err(|_| FontFaceError::FontOpeningError)?;
         let _ = file.read_to_end(&mut buf);
 
+        let face_names = face_names(&buf);
+        if face_index as usize >= face_names.len() {
+            return Err(FontFaceError::FaceIndex(face_index, face_names.len()));
+        }
+
+        let settings = fontdue::FontSettings {
+            collection_index: face_index,
+            ..Default::default()
+        };
+
+        let font = Font::from_bytes(buf.as_slice(), settings)
+            .map_err(|err| FontFaceError::CreationError(err))?;
+
         Ok(Self {
-            font: {
-                Font::from_bytes(buf, fontdue::FontSettings::default())
-                    .map_err(|err| FontFaceError::CreationError(err))?
-            },
+            metadata: FontMetadata::read(&buf, face_index, &font),
+            font,
             path: font_path,
+            face_index,
+            face_names,
+            fallbacks: vec![],
         })
     }
 
+    pub fn fallbacks(&self) -> &[FontFace] {
+        &self.fallbacks
+    }
+
+    pub fn add_fallback(&mut self, fallback: FontFace) {
+        self.fallbacks.push(fallback);
+    }
+
+    /// Swaps the fallback at `index` for another font, keeping its place in the chain.
+    pub fn replace_fallback(&mut self, index: usize, fallback: FontFace) {
+        if let Some(previous) = self.fallbacks.get_mut(index) {
+            *previous = fallback;
+        }
+    }
+
+    pub fn remove_fallback(&mut self, index: usize) {
+        if index < self.fallbacks.len() {
+            self.fallbacks.remove(index);
+        }
+    }
+
+    /// Moves the fallback chain over to another primary font.
+    pub fn take_fallbacks(&mut self) -> Vec<FontFace> {
+        std::mem::take(&mut self.fallbacks)
+    }
+
+    pub fn set_fallbacks(&mut self, fallbacks: Vec<FontFace>) {
+        self.fallbacks = fallbacks;
+    }
+
+    /// Stem of the font at `source` in the stack, 0 being this font and 1 its first fallback.
+    pub fn source_stem(&self, source: usize) -> &str {
+        match source {
+            0 => self.stem(),
+            _ => self.fallbacks.get(source - 1).map(|f| f.stem()).unwrap_or_default(),
+        }
+    }
+
+    /// Picks the first font in the stack that maps `c`, if any does.
+    ///
+    /// Returns the font's position in the stack and the pixel height to render it at, scaled
+    /// so its line height matches this font's at `pixel_height`.
+    fn font_for(&self, c: char, pixel_height: f32) -> Option<(usize, &Font, f32)> {
+        if self.font.lookup_glyph_index(c) != 0 {
+            return Some((0, &self.font, pixel_height));
+        }
+        let line_height = |font: &Font| font.horizontal_line_metrics(pixel_height).map(|l_m| l_m.ascent - l_m.descent);
+
+        self.fallbacks
+            .iter()
+            .enumerate()
+            .find(|(_, fallback)| fallback.font.lookup_glyph_index(c) != 0)
+            .map(|(i, fallback)| {
+                let scale = match (line_height(&self.font), line_height(&fallback.font)) {
+                    (Some(primary), Some(own)) if own > 0.0 => primary / own,
+                    _ => 1.0,
+                };
+                (i + 1, &fallback.font, pixel_height * scale)
+            })
+    }
+
+    pub fn metadata(&self) -> &FontMetadata {
+        &self.metadata
+    }
+
+    pub fn face_index(&self) -> u32 {
+        self.face_index
+    }
+
+    /// Names of every face in the file, indexed by face.
+    pub fn face_names(&self) -> &[String] {
+        &self.face_names
+    }
+
     pub fn stem(&self) -> &str {
         self.path.file_stem().unwrap().to_str().unwrap().into()
     }
@@ -47,12 +240,25 @@ impl FontFace {
         self.path.to_str().unwrap()
     }
 
+    pub fn path_buf(&self) -> PathBuf {
+        self.path.clone()
+    }
+
     pub fn chars(&self) -> Vec<char> {
         self.font.chars().keys().map(|c| *c).collect::<Vec<_>>()
     }
 
-    pub fn rasterize(&self, input: Option<String>, pixel_height: f32) -> (Option<LineMetrics>, Option<LineMetrics>, Rasterizations) {
-        let chars = if let Some(input) = input {
+    /// Rasterizes the settings' input, or every character of the font.
+    ///
+    /// Characters this font doesn't map come from the first fallback that does. Line metrics are
+    /// always this font's. Characters no font maps are handled by the missing glyph policy and
+    /// returned in input order. With a supersampling factor above 1 each glyph is rendered that many
+    /// times larger and filtered down into its 1× bitmap box. Synthetic oblique and bold are
+    /// applied after that.
+    pub fn rasterize(&self, render_settings: &RenderSettings) -> (Option<LineMetrics>, Option<LineMetrics>, Rasterizations, Vec<char>) {
+        let pixel_height = render_settings.render_height;
+        let supersampling = render_settings.supersampling;
+        let chars = if let Some(input) = render_settings.input.clone() {
             input.chars().collect()
         } else {
             self.chars()
@@ -61,16 +267,71 @@ impl FontFace {
         let h_line_metrics = self.font.horizontal_line_metrics(pixel_height);
         let v_line_metrics = self.font.vertical_line_metrics(pixel_height);
 
+        let mut missing = vec![];
         let rasters = chars
             .iter()
-            .map(|c| self.font.rasterize(*c, pixel_height))
-            .map(|raster| CharRaster::new(raster))
+            .filter_map(|c| {
+                let (source, font, pixel_height) = match self.font_for(*c, pixel_height) {
+                    Some(found) => found,
+                    None => {
+                        if !missing.contains(c) {
+                            missing.push(*c);
+                        }
+                        match render_settings.missing_glyphs {
+                            MissingGlyphPolicy::Skip => return None,
+                            // The primary font draws its .notdef glyph like any other.
+                            MissingGlyphPolicy::Notdef => (0, &self.font, pixel_height),
+                            MissingGlyphPolicy::EmptyCell => {
+                                let metrics = self.font.metrics(*c, pixel_height);
+                                return Some(CharRaster::new(*c, (fontdue::Metrics { width: 0, height: 0, ..metrics }, vec![])));
+                            },
+                        }
+                    },
+                };
+                let raster = if supersampling > 1 {
+                    let metrics = font.metrics(*c, pixel_height);
+                    let (ss_metrics, ss_pixels) = font.rasterize(*c, pixel_height * supersampling as f32);
+                    (metrics, downsample(&metrics, &ss_metrics, &ss_pixels, supersampling, render_settings.downsample_filter))
+                } else {
+                    font.rasterize(*c, pixel_height)
+                };
+                let raster = oblique(raster, render_settings.oblique_angle);
+                Some(CharRaster::new(*c, embolden(raster, render_settings.embolden)).with_source(source))
+            })
             .collect::<Vec<_>>() as Rasterizations;
 
         (
             h_line_metrics,
             v_line_metrics,
-            rasters
+            rasters,
+            missing
         )
     }
-}
\ No newline at end of file
+}
+
+/// Full name of each face in font data, or a numbered placeholder for faces without one.
+/// Plain font files count as a collection of one.
+fn face_names(data: &[u8]) -> Vec<String> {
+    let count = ttf_parser::fonts_in_collection(data).unwrap_or(1);
+    (0..count)
+        .map(|index| {
+            ttf_parser::Face::parse(data, index)
+                .ok()
+                .and_then(|face| {
+                    name_string(&face, ttf_parser::name_id::FULL_NAME)
+                        .or_else(|| name_string(&face, ttf_parser::name_id::FAMILY))
+                })
+                .unwrap_or(format!("Face {index}"))
+        })
+        .collect()
+}
+
+/// Decoded `name` table entry, preferring US English when the font has several languages.
+fn name_string(face: &ttf_parser::Face, name_id: u16) -> Option<String> {
+    let names = face.names().into_iter().filter(|n| n.name_id == name_id).collect::<Vec<_>>();
+    names
+        .iter()
+        .filter(|n| n.language() == ttf_parser::Language::English_UnitedStates)
+        .chain(&names)
+        .find_map(|n| n.to_string())
+}
@@ -0,0 +1,134 @@
+use std::{collections::BTreeMap, fmt::{Display, Write}, fs};
+
+use fontdue::LineMetrics;
+
+use crate::{
+    font_face::FontMetadata,
+    rasterization::Rasterizations,
+    renderer::{const_identifier, pack_bits, pick_save_path, RendererError},
+};
+
+#[derive(Debug)]
+pub enum GfxFontError {
+    FieldRange(u32, &'static str, i64),
+    LineHeight(usize),
+    WritingError(RendererError),
+}
+
+impl Display for GfxFontError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FieldRange(codepoint, field, value) => write!(f, "GFXglyph {field} of {value} for 0x{codepoint:02X} is out of range for its field."),
+            Self::LineHeight(height) => write!(f, "GFXfont yAdvance holds up to 255 pixels, lines are {height} high."),
+            Self::WritingError(err) => write!(f, "Encountered error writing GFXfont header: {err}."),
+        }
+    }
+}
+
+/// Checks `value` fits the C type `T` stands in for, GFX packs glyph fields tightly.
+fn check_field<T: TryFrom<i64>>(codepoint: u32, field: &'static str, value: i64) -> Result<i64, GfxFontError> {
+    T::try_from(value).map(|_| value).map_err(|_| GfxFontError::FieldRange(codepoint, field, value))
+}
+
+/// Generates an Adafruit GFX `GFXfont` header from thresholded rasterizations.
+///
+/// Glyph bitmaps are packed back to back without row padding, as `drawChar` expects.
+/// Codepoints missing between the first and last rendered character get empty glyphs,
+/// and characters above U+FFFF are left out since GFX indexes glyphs with 16 bits.
+pub fn generate_gfx_font(
+    stem: &str,
+    metadata: &FontMetadata,
+    h_line_metrics: Option<LineMetrics>,
+    rasterizations: &Rasterizations,
+    pixel_height: f32,
+    threshold: u8,
+) -> Result<String, GfxFontError> {
+    let name = format!("{}_{}px", const_identifier(stem).to_lowercase(), pixel_height.round());
+
+    let rasters = rasterizations
+        .iter()
+        .filter(|cr| (cr.get_char() as u32) <= 0xFFFF)
+        .map(|cr| (cr.get_char() as u32, cr))
+        .collect::<BTreeMap<_, _>>();
+
+    let first = rasters.keys().next().copied().unwrap_or(0);
+    let last = rasters.keys().next_back().copied().unwrap_or(0);
+
+    let y_advance = if let Some(l_m) = h_line_metrics {
+        l_m.new_line_size.round() as usize
+    } else {
+        rasters.values().map(|cr| cr.get_height()).max().unwrap_or(0)
+    };
+    if y_advance > u8::MAX as usize {
+        return Err(GfxFontError::LineHeight(y_advance));
+    }
+
+    let mut bitmap: Vec<u8> = vec![];
+    let mut glyphs = String::new();
+    for codepoint in first..=last {
+        let offset = check_field::<u16>(codepoint, "bitmapOffset", bitmap.len() as i64)?;
+        let Some(raster) = rasters.get(&codepoint) else {
+            let _ = writeln!(glyphs, "    {{{offset:5},   0,   0,   0,    0,    0}}, // 0x{codepoint:02X} (missing)");
+            continue;
+        };
+
+        let metrics = raster.get_metrics();
+        let bits = raster.get_bitmap(threshold);
+        bitmap.extend(pack_bits(&bits, bits.len()));
+
+        let label = char::from_u32(codepoint)
+            .filter(|c| c.is_ascii_graphic() || *c == ' ')
+            .map(|c| format!(" '{c}'"))
+            .unwrap_or_default();
+        let _ = writeln!(
+            glyphs,
+            "    {{{offset:5}, {:3}, {:3}, {:3}, {:4}, {:4}}}, // 0x{codepoint:02X}{label}",
+            check_field::<u8>(codepoint, "width", metrics.width as i64)?,
+            check_field::<u8>(codepoint, "height", metrics.height as i64)?,
+            check_field::<u8>(codepoint, "xAdvance", metrics.advance_width.round() as i64)?,
+            check_field::<i8>(codepoint, "xOffset", metrics.xmin as i64)?,
+            check_field::<i8>(codepoint, "yOffset", -(metrics.ymin + metrics.height as i32) as i64)?,
+        );
+    }
+
+    let mut header = String::new();
+    let _ = writeln!(header, "// Generated by Font Rasterizer from {stem}.");
+    header.push_str(&metadata.comment_block("//"));
+    let _ = writeln!(header, "#pragma once");
+    let _ = writeln!(header, "#include <Adafruit_GFX.h>\n");
+    let _ = writeln!(header, "const uint8_t {name}_bitmaps[] PROGMEM = {{");
+    for line in bitmap.chunks(16) {
+        let bytes = line.iter().map(|b| format!("0x{b:02X}")).collect::<Vec<_>>().join(", ");
+        let _ = writeln!(header, "    {bytes},");
+    }
+    if bitmap.is_empty() {
+        let _ = writeln!(header, "    0x00,");
+    }
+    let _ = writeln!(header, "}};\n");
+    let _ = writeln!(header, "// bitmapOffset, width, height, xAdvance, xOffset, yOffset");
+    let _ = writeln!(header, "const GFXglyph {name}_glyphs[] PROGMEM = {{");
+    header.push_str(&glyphs);
+    let _ = writeln!(header, "}};\n");
+    let _ = writeln!(header, "const GFXfont {name} PROGMEM = {{");
+    let _ = writeln!(header, "    (uint8_t *){name}_bitmaps,");
+    let _ = writeln!(header, "    (GFXglyph *){name}_glyphs,");
+    let _ = writeln!(header, "    0x{first:02X}, 0x{last:02X}, {y_advance}");
+    let _ = writeln!(header, "}};");
+
+    Ok(header)
+}
+
+pub fn write_gfx_font(
+    stem: &str,
+    metadata: &FontMetadata,
+    h_line_metrics: Option<LineMetrics>,
+    rasterizations: &Rasterizations,
+    pixel_height: f32,
+    threshold: u8,
+) -> Result<(), GfxFontError> {
+    let header = generate_gfx_font(stem, metadata, h_line_metrics, rasterizations, pixel_height, threshold)?;
+    let name = format!("{}_{}px", const_identifier(stem).to_lowercase(), pixel_height.round());
+    let header_path = pick_save_path(name, "C header", "h").map_err(GfxFontError::WritingError)?;
+
+    fs::write(header_path, header).map_err(|_| GfxFontError::WritingError(RendererError::CreationError))
+}
@@ -0,0 +1,65 @@
+use std::{fmt::Write, fs, path::Path};
+
+use crate::{font_face::FontMetadata, renderer::{RenderData, RenderInfo, RendererError}};
+
+/// Builds a Godot 4 `FontFile` resource whose glyphs are the cells of the rendered atlas.
+///
+/// The atlas is embedded as an `LA8` image with coverage in alpha, so the resource
+/// does not depend on how the exported PNG gets imported.
+pub fn generate_font_file(metadata: &FontMetadata, render_data: &RenderData, render_info: &RenderInfo, fixed_size: usize) -> String {
+    let (cell_width, cell_height) = render_info.cell_size();
+    let baseline = render_info.baseline();
+    let cache = format!("cache/0/{fixed_size}/0");
+
+    let mut image_data = String::new();
+    for (i, value) in render_data.pixels().iter().enumerate() {
+        if i > 0 {
+            image_data.push_str(", ");
+        }
+        let _ = write!(image_data, "255, {value}");
+    }
+
+    let mut resource = String::new();
+    let _ = writeln!(resource, "[gd_resource type=\"FontFile\" load_steps=2 format=3]\n");
+    let notice = metadata.comment_block(";");
+    if !notice.is_empty() {
+        let _ = writeln!(resource, "{notice}");
+    }
+    let _ = writeln!(resource, "[sub_resource type=\"Image\" id=\"Image_atlas\"]");
+    let _ = writeln!(resource, "data = {{");
+    let _ = writeln!(resource, "\"data\": PackedByteArray({image_data}),");
+    let _ = writeln!(resource, "\"format\": \"LA8\",");
+    let _ = writeln!(resource, "\"height\": {},", render_data.height());
+    let _ = writeln!(resource, "\"mipmaps\": false,");
+    let _ = writeln!(resource, "\"width\": {}", render_data.width());
+    let _ = writeln!(resource, "}}\n");
+
+    let _ = writeln!(resource, "[resource]");
+    let _ = writeln!(resource, "fixed_size = {fixed_size}");
+    let _ = writeln!(resource, "{cache}/ascent = {baseline}.0");
+    let _ = writeln!(resource, "{cache}/descent = {}.0", cell_height.saturating_sub(baseline));
+    let _ = writeln!(resource, "{cache}/underline_position = 0.0");
+    let _ = writeln!(resource, "{cache}/underline_thickness = 0.0");
+    let _ = writeln!(resource, "{cache}/scale = 1.0");
+    let _ = writeln!(resource, "{cache}/textures/0/offsets = PackedInt32Array()");
+    let _ = writeln!(resource, "{cache}/textures/0/image = SubResource(\"Image_atlas\")");
+
+    for (idx, (character, metrics)) in render_info.cell_glyphs().iter().enumerate() {
+        let (x, y) = render_info.cell_origin(idx);
+        // Glyphs are centered horizontally in their cell, shift back so the pen lands on xmin.
+        let width_offset = cell_width.saturating_sub(metrics.width).div_ceil(2) as i32;
+        let glyph = format!("{cache}/glyphs/{}", *character as u32);
+
+        let _ = writeln!(resource, "{glyph}/advance = Vector2({}, 0)", metrics.advance_width.round());
+        let _ = writeln!(resource, "{glyph}/offset = Vector2({}, -{baseline})", metrics.xmin - width_offset);
+        let _ = writeln!(resource, "{glyph}/size = Vector2({cell_width}, {cell_height})");
+        let _ = writeln!(resource, "{glyph}/uv_rect = Rect2({x}, {y}, {cell_width}, {cell_height})");
+        let _ = writeln!(resource, "{glyph}/texture_idx = 0");
+    }
+
+    resource
+}
+
+pub fn write_font_file(path: &Path, metadata: &FontMetadata, render_data: &RenderData, render_info: &RenderInfo, fixed_size: usize) -> Result<(), RendererError> {
+    fs::write(path, generate_font_file(metadata, render_data, render_info, fixed_size)).map_err(|_| RendererError::CreationError)
+}
@@ -1,4 +1,14 @@
 pub mod app;
+pub mod bdf;
+pub mod c_header;
+pub mod channel_pack;
+pub mod effects;
+pub mod encoders;
 pub mod font_face;
+pub mod gfx_font;
+pub mod godot;
+pub mod mono_font;
+pub mod psf;
 pub mod rasterization;
-pub mod renderer;
\ No newline at end of file
+pub mod renderer;
+pub mod shader;
\ No newline at end of file
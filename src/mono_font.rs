@@ -0,0 +1,86 @@
+use std::{fmt::{Display, Write}, fs};
+
+use crate::{
+    font_face::FontMetadata,
+    renderer::{const_identifier, pack_bits, pick_save_path, RenderData, RenderDirection, RenderInfo, RendererError},
+};
+
+#[derive(Debug)]
+pub enum MonoFontError {
+    NoGlyphs,
+    UnsupportedDirection,
+    WritingError(RendererError),
+}
+
+impl Display for MonoFontError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoGlyphs => write!(f, "No glyphs were rendered."),
+            Self::UnsupportedDirection => write!(f, "MonoFont expects glyphs laid out left to right."),
+            Self::WritingError(err) => write!(f, "Encountered error writing MonoFont source: {err}."),
+        }
+    }
+}
+
+/// Generates Rust source declaring an `embedded-graphics` 0.8 `MonoFont` for the atlas.
+///
+/// MonoFont indexes glyphs row by row across the image, which matches the padded cell
+/// grid of a left to right render, so the atlas is packed to 1 bpp as is.
+pub fn generate_mono_font(stem: &str, metadata: &FontMetadata, render_data: &RenderData, render_info: &RenderInfo, threshold: u8) -> Result<String, MonoFontError> {
+    if render_info.render_direction() != RenderDirection::LeftToRight {
+        return Err(MonoFontError::UnsupportedDirection);
+    }
+    let glyphs = render_info.cell_glyphs();
+    if glyphs.is_empty() {
+        return Err(MonoFontError::NoGlyphs);
+    }
+
+    let (cell_width, cell_height) = render_info.cell_size();
+    let (left, right, up, down) = render_info.cell_padding();
+    let character_width = cell_width + left + right;
+    let character_height = cell_height + up + down;
+    let baseline = up + render_info.baseline();
+
+    // Rows of an ImageRaw<BinaryColor> start on a byte boundary, most significant bit first.
+    let bits = render_data.pixels().iter().map(|v| *v >= threshold).collect::<Vec<_>>();
+    let image = pack_bits(&bits, render_data.width());
+
+    // A NUL in the mapping string starts a character range, so it can't be mapped directly.
+    let mapping = glyphs
+        .iter()
+        .map(|(c, _)| if *c == '\0' { ' ' } else { *c })
+        .collect::<String>();
+    let replacement = glyphs.iter().position(|(c, _)| *c == '?').unwrap_or(0);
+
+    let mut source = String::new();
+    let _ = writeln!(source, "// Generated by Font Rasterizer from {stem}.");
+    source.push_str(&metadata.comment_block("//"));
+    let _ = writeln!(source, "use embedded_graphics::{{");
+    let _ = writeln!(source, "    geometry::Size,");
+    let _ = writeln!(source, "    image::ImageRaw,");
+    let _ = writeln!(source, "    mono_font::{{mapping::StrGlyphMapping, DecorationDimensions, MonoFont}},");
+    let _ = writeln!(source, "}};\n");
+    let _ = writeln!(source, "pub const {}: MonoFont = MonoFont {{", const_identifier(stem));
+    let _ = writeln!(source, "    image: ImageRaw::new(&[");
+    for line in image.chunks(16) {
+        let bytes = line.iter().map(|b| format!("0x{b:02x}")).collect::<Vec<_>>().join(", ");
+        let _ = writeln!(source, "        {bytes},");
+    }
+    let _ = writeln!(source, "    ], {}),", render_data.width());
+    let _ = writeln!(source, "    glyph_mapping: &StrGlyphMapping::new({mapping:?}, {replacement}),");
+    let _ = writeln!(source, "    character_size: Size::new({character_width}, {character_height}),");
+    let _ = writeln!(source, "    character_spacing: 0,");
+    let _ = writeln!(source, "    baseline: {baseline},");
+    let _ = writeln!(source, "    underline: DecorationDimensions::new({}, 1),", (baseline + 2).min(character_height.saturating_sub(1)));
+    let _ = writeln!(source, "    strikethrough: DecorationDimensions::new({}, 1),", character_height / 2);
+    let _ = writeln!(source, "}};");
+
+    Ok(source)
+}
+
+pub fn write_mono_font(stem: &str, metadata: &FontMetadata, render_data: &RenderData, render_info: &RenderInfo, threshold: u8) -> Result<(), MonoFontError> {
+    let source = generate_mono_font(stem, metadata, render_data, render_info, threshold)?;
+    let source_path = pick_save_path(const_identifier(stem).to_lowercase(), "rust", "rs").map_err(MonoFontError::WritingError)?;
+
+    fs::write(source_path, source).map_err(|_| MonoFontError::WritingError(RendererError::CreationError))
+}
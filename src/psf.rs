@@ -0,0 +1,92 @@
+use std::{fmt::Display, fs};
+
+use crate::{
+    font_face::FontMetadata,
+    renderer::{pack_bits, pick_save_path, write_font_notice, RenderData, RenderInfo, RendererError},
+};
+
+const PSF2_MAGIC: u32 = 0x864a_b572;
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+const PSF2_SEPARATOR: u8 = 0xFF;
+
+/// Largest glyph count the Linux console accepts.
+pub const PSF2_MAX_GLYPHS: usize = 512;
+/// Largest glyph width and height the Linux console accepts.
+pub const PSF2_MAX_CELL_SIZE: usize = 32;
+
+#[derive(Debug)]
+pub enum PsfError {
+    GlyphCount(usize),
+    CellSize(usize, usize),
+    WritingError(RendererError),
+}
+
+impl Display for PsfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::GlyphCount(count) => write!(f, "PSF2 fonts hold 1 to {PSF2_MAX_GLYPHS} glyphs, {count} were rendered."),
+            Self::CellSize(w, h) => write!(f, "PSF2 cells must be 1 to {PSF2_MAX_CELL_SIZE} pixels wide and high, padded cells are {w}x{h}."),
+            Self::WritingError(err) => write!(f, "Encountered error writing PSF2 font: {err}."),
+        }
+    }
+}
+
+/// Packs each padded atlas cell into a 1 bpp PSF2 glyph, with a Unicode table mapping
+/// glyphs back to the characters they were rendered from.
+pub fn generate_psf2(render_data: &RenderData, render_info: &RenderInfo, threshold: u8) -> Result<Vec<u8>, PsfError> {
+    let (cell_width, cell_height) = render_info.cell_size();
+    let (left, right, up, down) = render_info.cell_padding();
+    let width = cell_width + left + right;
+    let height = cell_height + up + down;
+    let glyphs = render_info.cell_glyphs();
+
+    if glyphs.is_empty() || glyphs.len() > PSF2_MAX_GLYPHS {
+        return Err(PsfError::GlyphCount(glyphs.len()));
+    }
+    if width == 0 || height == 0 || width > PSF2_MAX_CELL_SIZE || height > PSF2_MAX_CELL_SIZE {
+        return Err(PsfError::CellSize(width, height));
+    }
+
+    let glyph_size = width.div_ceil(8) * height;
+
+    let mut psf = Vec::with_capacity(32 + glyphs.len() * (glyph_size + 8));
+    for field in [
+        PSF2_MAGIC,
+        0,
+        32,
+        PSF2_HAS_UNICODE_TABLE,
+        glyphs.len() as u32,
+        glyph_size as u32,
+        height as u32,
+        width as u32,
+    ] {
+        psf.extend_from_slice(&field.to_le_bytes());
+    }
+
+    for idx in 0..glyphs.len() {
+        let (x, y) = render_info.cell_origin(idx);
+        let (x, y) = (x - left, y - up);
+        let bits = (y..y + height)
+            .flat_map(|row| &render_data.pixels()[row * render_data.width() + x..][..width])
+            .map(|v| *v >= threshold)
+            .collect::<Vec<_>>();
+        psf.extend(pack_bits(&bits, width));
+    }
+
+    for (character, _) in glyphs {
+        let mut buf = [0u8; 4];
+        psf.extend_from_slice(character.encode_utf8(&mut buf).as_bytes());
+        psf.push(PSF2_SEPARATOR);
+    }
+
+    Ok(psf)
+}
+
+/// Writes the font, then the font notice next to it since PSF2 has no room for names or a license.
+pub fn write_psf2(name: String, metadata: &FontMetadata, render_data: &RenderData, render_info: &RenderInfo, threshold: u8) -> Result<(), PsfError> {
+    let psf = generate_psf2(render_data, render_info, threshold)?;
+    let psf_path = pick_save_path(name, "psf", "psf").map_err(PsfError::WritingError)?;
+
+    fs::write(&psf_path, psf).map_err(|_| PsfError::WritingError(RendererError::CreationError))?;
+    write_font_notice(&psf_path, metadata).map_err(PsfError::WritingError)
+}
@@ -1,6 +1,8 @@
 use fontdue::Metrics;
 use std::{collections::{HashMap, HashSet}, fmt::Display};
 
+use crate::effects::{composite_over, grow, EffectSettings};
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum RasterizationProperty {
     Brightness,
@@ -18,20 +20,493 @@ impl Display for RasterizationProperty {
     }
 }
 
+/// Curve applied to fontdue's linear coverage before placement.
+#[derive(Clone, Copy, PartialEq)]
+pub enum CoverageTransfer {
+    Linear,
+    /// Encodes coverage with the given gamma, `coverage^(1 / gamma)`.
+    Gamma(f32),
+    /// Symmetric S-curve around half coverage, steeper as the factor grows past 1.
+    Contrast(f32),
+    LinearToSrgb,
+}
+
+impl CoverageTransfer {
+    fn apply(self, coverage: f32) -> f32 {
+        match self {
+            Self::Linear => coverage,
+            Self::Gamma(gamma) => coverage.powf(1.0 / gamma),
+            Self::Contrast(factor) => {
+                let a = coverage.powf(factor);
+                let b = (1.0 - coverage).powf(factor);
+                if a + b > 0.0 { a / (a + b) } else { coverage }
+            },
+            Self::LinearToSrgb => {
+                if coverage <= 0.003_130_8 {
+                    coverage * 12.92
+                } else {
+                    1.055 * coverage.powf(1.0 / 2.4) - 0.055
+                }
+            },
+        }
+    }
+
+    /// Lookup table mapping every 8-bit coverage value through the curve.
+    pub fn table(self) -> [u8; 256] {
+        let mut table = [0u8; 256];
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = (self.apply(i as f32 / 255.0).clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        table
+    }
+}
+
+impl Display for CoverageTransfer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Linear => write!(f, "Linear"),
+            Self::Gamma(gamma) => write!(f, "Gamma ({gamma})"),
+            Self::Contrast(factor) => write!(f, "Contrast ({factor})"),
+            Self::LinearToSrgb => write!(f, "Linear to sRGB"),
+        }
+    }
+}
+
+/// Filter used to bring a supersampled glyph back down to the target size.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum DownsampleFilter {
+    /// Averages each k×k block of samples.
+    #[default]
+    Box,
+    /// Two-lobe Lanczos, sharper than a box but can ring slightly around stems.
+    Lanczos,
+}
+
+impl DownsampleFilter {
+    /// Filter weight at a distance measured in target pixels.
+    fn weight(self, distance: f32) -> f32 {
+        match self {
+            Self::Box => if distance.abs() < 0.5 { 1.0 } else { 0.0 },
+            Self::Lanczos => {
+                let d = distance.abs();
+                if d < 1e-6 {
+                    1.0
+                } else if d < 2.0 {
+                    let x = std::f32::consts::PI * d;
+                    2.0 * x.sin() * (x / 2.0).sin() / (x * x)
+                } else {
+                    0.0
+                }
+            },
+        }
+    }
+
+    /// Half width of the filter in target pixels.
+    fn support(self) -> f32 {
+        match self {
+            Self::Box => 0.5,
+            Self::Lanczos => 2.0,
+        }
+    }
+}
+
+impl Display for DownsampleFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Box => write!(f, "Box"),
+            Self::Lanczos => write!(f, "Lanczos"),
+        }
+    }
+}
+
+/// Resamples a glyph rasterized at `factor` times the size into the bitmap box of `target`,
+/// the glyph's metrics at the target size.
+///
+/// Both bitmaps are placed by their own `xmin`/`ymin`, so the result lines up with the 1×
+/// metrics and line metrics exactly. Samples outside the supersampled bitmap count as empty.
+pub fn downsample(target: &Metrics, metrics: &Metrics, pixels: &[u8], factor: usize, filter: DownsampleFilter) -> Vec<u8> {
+    let k = factor as f32;
+    let reach = (filter.support() * k).ceil() as i32;
+    let sample = |x: i32, y: i32| -> f32 {
+        // `y` counts up from the baseline, rows are stored top to bottom.
+        let column = x - metrics.xmin;
+        let row = metrics.ymin + metrics.height as i32 - 1 - y;
+        if column < 0 || row < 0 || column >= metrics.width as i32 || row >= metrics.height as i32 {
+            0.0
+        } else {
+            pixels[row as usize * metrics.width + column as usize] as f32
+        }
+    };
+
+    let mut downsampled = Vec::with_capacity(target.width * target.height);
+    for row in 0..target.height as i32 {
+        let y = target.ymin + target.height as i32 - 1 - row;
+        let center_y = (y as f32 + 0.5) * k;
+        for column in 0..target.width as i32 {
+            let x = target.xmin + column;
+            let center_x = (x as f32 + 0.5) * k;
+
+            let (mut sum, mut weights) = (0.0, 0.0);
+            for sy in (center_y as i32 - reach)..=(center_y as i32 + reach) {
+                let wy = filter.weight((sy as f32 + 0.5 - center_y) / k);
+                if wy == 0.0 {
+                    continue;
+                }
+                for sx in (center_x as i32 - reach)..=(center_x as i32 + reach) {
+                    let w = wy * filter.weight((sx as f32 + 0.5 - center_x) / k);
+                    sum += w * sample(sx, sy);
+                    weights += w;
+                }
+            }
+            let value = if weights > 0.0 { sum / weights } else { 0.0 };
+            downsampled.push(value.round().clamp(0.0, 255.0) as u8);
+        }
+    }
+    downsampled
+}
+
+/// Reduces coverage to fully on or off pixels, for 1-bit outputs.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Dithering {
+    #[default]
+    None,
+    /// Ordered dithering with an 8×8 Bayer matrix anchored at each glyph's top left.
+    Bayer,
+    FloydSteinberg,
+    Atkinson,
+}
+
+impl Dithering {
+    /// Error diffusion kernel as `(dx, dy, weight)` and the weight divisor.
+    fn kernel(self) -> (&'static [(isize, usize, i32)], i32) {
+        match self {
+            Self::FloydSteinberg => (&[(1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1)], 16),
+            // Atkinson only spreads 6/8 of the error, which keeps thin strokes crisp.
+            Self::Atkinson => (&[(1, 0, 1), (2, 0, 1), (-1, 1, 1), (0, 1, 1), (1, 1, 1), (0, 2, 1)], 8),
+            Self::None | Self::Bayer => (&[], 1),
+        }
+    }
+
+    fn bayer_threshold(x: usize, y: usize) -> u8 {
+        let mut index = 0;
+        for bit in 0..3 {
+            let (xb, yb) = ((x >> bit) & 1, (y >> bit) & 1);
+            index |= ((xb ^ yb) << 1 | yb) << (2 * (2 - bit));
+        }
+        ((index * 4 + 2) as f32 * 255.0 / 256.0) as u8
+    }
+
+    /// Dithers one glyph bitmap in place. Error never leaves the bitmap, so neighboring cells
+    /// don't influence each other.
+    fn apply(self, pixels: &mut [u8], width: usize) {
+        match self {
+            Self::None => (),
+            Self::Bayer => {
+                for (i, v) in pixels.iter_mut().enumerate() {
+                    *v = if *v > Self::bayer_threshold(i % width, i / width) { 255 } else { 0 };
+                }
+            },
+            Self::FloydSteinberg | Self::Atkinson => {
+                let height = pixels.len() / width.max(1);
+                let (kernel, divisor) = self.kernel();
+                let mut values = pixels.iter().map(|v| *v as i32).collect::<Vec<_>>();
+                for y in 0..height {
+                    for x in 0..width {
+                        let old = values[y * width + x];
+                        let new = if old >= 128 { 255 } else { 0 };
+                        pixels[y * width + x] = new as u8;
+                        let error = old - new;
+                        for (dx, dy, weight) in kernel {
+                            let (nx, ny) = (x as isize + dx, y + dy);
+                            if nx >= 0 && (nx as usize) < width && ny < height {
+                                values[ny * width + nx as usize] += error * weight / divisor;
+                            }
+                        }
+                    }
+                }
+            },
+        }
+    }
+}
+
+impl Display for Dithering {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Bayer => write!(f, "Bayer (ordered)"),
+            Self::FloydSteinberg => write!(f, "Floyd–Steinberg"),
+            Self::Atkinson => write!(f, "Atkinson"),
+        }
+    }
+}
+
+/// Shears a glyph by `angle` degrees around the baseline, positive angles lean to the right.
+///
+/// Rows are shifted by their height above the baseline and resampled linearly, the bitmap
+/// box widens to fit. The advance width is kept, as with most synthetic obliques.
+pub fn oblique((metrics, pixels): (Metrics, Vec<u8>), angle: f32) -> (Metrics, Vec<u8>) {
+    let slant = angle.to_radians().tan();
+    if slant == 0.0 || metrics.width == 0 || metrics.height == 0 {
+        return (metrics, pixels);
+    }
+
+    // Shift of each row, measured at the row's center.
+    let shift = |row: usize| (metrics.ymin as f32 + (metrics.height - row) as f32 - 0.5) * slant;
+    let (top, bottom) = (shift(0), shift(metrics.height - 1));
+    let xmin = metrics.xmin + top.min(bottom).floor() as i32;
+    let xmax = metrics.xmin + metrics.width as i32 + top.max(bottom).ceil() as i32;
+    let width = (xmax - xmin) as usize;
+
+    let mut sheared = vec![0u8; width * metrics.height];
+    for row in 0..metrics.height {
+        let source = &pixels[row * metrics.width..(row + 1) * metrics.width];
+        let sample = |column: i32| if column < 0 { 0.0 } else { source.get(column as usize).copied().unwrap_or(0) as f32 };
+        for column in 0..width {
+            let position = (xmin + column as i32 - metrics.xmin) as f32 - shift(row);
+            let left = position.floor();
+            let t = position - left;
+            let value = sample(left as i32) * (1.0 - t) + sample(left as i32 + 1) * t;
+            sheared[row * width + column] = value.round() as u8;
+        }
+    }
+
+    let mut metrics = metrics;
+    let (bottom, top) = (metrics.bounds.ymin * slant, (metrics.bounds.ymin + metrics.bounds.height) * slant);
+    metrics.bounds.xmin += bottom.min(top);
+    metrics.bounds.width += (top - bottom).abs();
+    metrics.xmin = xmin;
+    metrics.width = width;
+    (metrics, sheared)
+}
+
+/// Smears a glyph `strength` pixels to the right, a fractional part blends in one more column.
+/// The bitmap and the advance width grow by the same amount.
+pub fn embolden((metrics, pixels): (Metrics, Vec<u8>), strength: f32) -> (Metrics, Vec<u8>) {
+    if strength <= 0.0 {
+        return (metrics, pixels);
+    }
+    // Blank glyphs have nothing to smear but still advance as far as bold letters do.
+    if metrics.width == 0 {
+        let mut metrics = metrics;
+        metrics.advance_width += strength;
+        return (metrics, pixels);
+    }
+
+    let whole = strength.floor() as usize;
+    let fraction = strength - whole as f32;
+    let extra = strength.ceil() as usize;
+    let width = metrics.width + extra;
+
+    let mut bold = vec![0u8; width * metrics.height];
+    for (row, source) in pixels.chunks(metrics.width).enumerate() {
+        for column in 0..width {
+            let sample = |offset: usize| column.checked_sub(offset).and_then(|c| source.get(c)).copied().unwrap_or(0);
+            let mut value = (0..=whole).map(sample).max().unwrap_or(0);
+            if fraction > 0.0 {
+                value = value.max((sample(extra) as f32 * fraction).round() as u8);
+            }
+            bold[row * width + column] = value;
+        }
+    }
+
+    let mut metrics = metrics;
+    metrics.width = width;
+    metrics.advance_width += strength;
+    metrics.bounds.width += strength;
+    (metrics, bold)
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise,
+    HalfTurn,
+    CounterClockwise,
+}
+
+impl Display for Rotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Clockwise => write!(f, "90° clockwise"),
+            Self::HalfTurn => write!(f, "180°"),
+            Self::CounterClockwise => write!(f, "90° counter-clockwise"),
+        }
+    }
+}
+
+/// Per-glyph transform applied before placement. Mirroring happens before rotation.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct GlyphTransform {
+    pub rotation: Rotation,
+    pub mirror_horizontal: bool,
+    pub mirror_vertical: bool,
+    /// Dark glyph on a lit cell, `generate_render_data` lights the rest of each cell.
+    pub invert: bool,
+}
+
+impl GlyphTransform {
+    pub fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// Whether glyphs are rotated or mirrored, and so may no longer fit the line metrics.
+    pub fn moves_glyphs(&self) -> bool {
+        self.rotation != Rotation::None || self.mirror_horizontal || self.mirror_vertical
+    }
+
+    /// Mirrors then rotates an offset from the center of rotation, y pointing up.
+    fn map(&self, (mut dx, mut dy): (f32, f32)) -> (f32, f32) {
+        if self.mirror_horizontal {
+            dx = -dx;
+        }
+        if self.mirror_vertical {
+            dy = -dy;
+        }
+        match self.rotation {
+            Rotation::None => (dx, dy),
+            Rotation::Clockwise => (dy, -dx),
+            Rotation::HalfTurn => (-dx, -dy),
+            Rotation::CounterClockwise => (-dy, dx),
+        }
+    }
+
+    /// Moves a box given as (xmin, ymin, width, height) around `center`.
+    fn map_box(&self, (x, y, width, height): (f32, f32, f32, f32), (cx, cy): (f32, f32)) -> (f32, f32, f32, f32) {
+        let (ax, ay) = self.map((x - cx, y - cy));
+        let (bx, by) = self.map((x + width - cx, y + height - cy));
+        (cx + ax.min(bx), cy + ay.min(by), (ax - bx).abs(), (ay - by).abs())
+    }
+}
+
+/// What to render for input characters no font in the stack maps.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum MissingGlyphPolicy {
+    Skip,
+    /// Render the primary font's `.notdef` glyph, usually a box.
+    #[default]
+    Notdef,
+    /// Keep the character's cell, but leave it blank.
+    EmptyCell,
+}
+
+impl Display for MissingGlyphPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Skip => write!(f, "Skip"),
+            Self::Notdef => write!(f, "Keep .notdef"),
+            Self::EmptyCell => write!(f, "Empty cell"),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct CharRaster {
+    character: char,
     metrics: Metrics,
     brightness: usize,
     pixels: Vec<u8>,
+    effect: Option<Vec<u8>>,
+    /// Position in the font stack of the font the glyph was rasterized from.
+    source: usize,
 }
 
 impl CharRaster {
-    pub fn new((metrics, pixels): (Metrics, Vec<u8>)) -> Self {
+    pub fn new(character: char, (metrics, pixels): (Metrics, Vec<u8>)) -> Self {
         Self {
+            character,
             metrics,
             brightness: pixels.iter().map(|v| *v as usize).sum(),
             pixels,
+            effect: None,
+            source: 0,
+        }
+    }
+
+    pub fn with_source(mut self, source: usize) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Replaces every pixel through `table`, keeping brightness in sync.
+    pub fn map_pixels(&mut self, table: &[u8; 256]) {
+        self.pixels.iter_mut().for_each(|v| *v = table[*v as usize]);
+        self.brightness = self.pixels.iter().map(|v| *v as usize).sum();
+    }
+
+    pub fn dither(&mut self, dithering: Dithering) {
+        dithering.apply(&mut self.pixels, self.metrics.width);
+        self.brightness = self.pixels.iter().map(|v| *v as usize).sum();
+    }
+
+    /// Grows the bitmap by the effect extent, then merges the effect into the coverage or keeps
+    /// it aside for its own channel.
+    pub fn apply_effects(&mut self, effects: &EffectSettings) {
+        let (metrics, mut pixels) = grow(self.metrics, &self.pixels, effects.extent());
+        let effect = effects.apply(&pixels, metrics.width, metrics.height);
+        if effects.separate_channel {
+            self.effect = Some(effect);
+        } else {
+            pixels.iter_mut().zip(effect).for_each(|(v, e)| *v = composite_over(*v, e));
         }
+        self.metrics = metrics;
+        self.pixels = pixels;
+        self.brightness = self.pixels.iter().map(|v| *v as usize).sum();
+    }
+
+    /// Mirrors and rotates the glyph around the middle of its advance at height `center_y`,
+    /// so it stays in place within its cell.
+    pub fn transform(&mut self, transform: GlyphTransform, center_y: f32) {
+        if !transform.moves_glyphs() {
+            return;
+        }
+        let metrics = self.metrics;
+        let (width, height) = match transform.rotation {
+            Rotation::Clockwise | Rotation::CounterClockwise => (metrics.height, metrics.width),
+            Rotation::None | Rotation::HalfTurn => (metrics.width, metrics.height),
+        };
+
+        let mut pixels = vec![0u8; self.pixels.len()];
+        let mut effect = self.effect.as_ref().map(|e| vec![0u8; e.len()]);
+        for (i, value) in self.pixels.iter().enumerate() {
+            let (column, row) = (i % metrics.width, i / metrics.width);
+            let (dx, dy) = transform.map((
+                column as f32 + 0.5 - metrics.width as f32 / 2.0,
+                metrics.height as f32 / 2.0 - row as f32 - 0.5,
+            ));
+            let column = (dx + width as f32 / 2.0 - 0.5).round() as usize;
+            let row = (height as f32 / 2.0 - dy - 0.5).round() as usize;
+            pixels[row * width + column] = *value;
+            if let (Some(effect), Some(source)) = (&mut effect, &self.effect) {
+                effect[row * width + column] = source[i];
+            }
+        }
+
+        let center = (metrics.advance_width / 2.0, center_y);
+        let (xmin, ymin, _, _) = transform.map_box(
+            (metrics.xmin as f32, metrics.ymin as f32, metrics.width as f32, metrics.height as f32),
+            center,
+        );
+        let bounds = transform.map_box(
+            (metrics.bounds.xmin, metrics.bounds.ymin, metrics.bounds.width, metrics.bounds.height),
+            center,
+        );
+
+        self.metrics.xmin = xmin.round() as i32;
+        self.metrics.ymin = ymin.round() as i32;
+        self.metrics.width = width;
+        self.metrics.height = height;
+        (self.metrics.bounds.xmin, self.metrics.bounds.ymin, self.metrics.bounds.width, self.metrics.bounds.height) = bounds;
+        self.pixels = pixels;
+        self.effect = effect;
+    }
+
+    /// Inverts coverage within the glyph box. `lit` is the brightness of a fully lit cell, any
+    /// value shared by the whole set keeps brightness ordering correct.
+    pub fn invert(&mut self, lit: usize) {
+        self.pixels.iter_mut().for_each(|v| *v = 255 - *v);
+        self.brightness = lit.saturating_sub(self.brightness);
     }
 
     fn get_property(&self, property: RasterizationProperty) -> usize {
@@ -42,6 +517,14 @@ impl CharRaster {
         }
     }
 
+    pub fn get_char(&self) -> char {
+        self.character
+    }
+
+    pub fn get_source(&self) -> usize {
+        self.source
+    }
+
     pub fn get_metrics(&self) -> Metrics {
         self.metrics
     }
@@ -50,6 +533,16 @@ impl CharRaster {
         self.pixels.clone()
     }
 
+    /// Effect coverage aligned with the pixels, when effects are kept in their own channel.
+    pub fn get_effect(&self) -> Option<&[u8]> {
+        self.effect.as_deref()
+    }
+
+    /// Pixels whose coverage reaches `threshold`, row by row.
+    pub fn get_bitmap(&self, threshold: u8) -> Vec<bool> {
+        self.pixels.iter().map(|v| *v >= threshold).collect()
+    }
+
     pub fn get_brightness(&self) -> usize {
         self.brightness
     }
@@ -69,6 +562,11 @@ pub trait RasterManip {
     fn sort_rasters_by(&mut self, property: RasterizationProperty);
     fn dedup_rasters_by(&mut self, property: RasterizationProperty);
     fn dedup_exact_duplicate(&mut self);
+    fn apply_transfer(&mut self, transfer: CoverageTransfer);
+    fn apply_dithering(&mut self, dithering: Dithering);
+    fn apply_effects(&mut self, effects: &EffectSettings);
+    fn apply_transform(&mut self, transform: GlyphTransform, center_y: f32);
+    fn apply_inversion(&mut self, invert: bool);
 }
 
 impl RasterManip for Rasterizations {
@@ -98,6 +596,45 @@ impl RasterManip for Rasterizations {
         let mut set = HashSet::new();
         self.retain(|cr| set.insert(cr.pixels.clone()));
     }
+
+    fn apply_transfer(&mut self, transfer: CoverageTransfer) {
+        if transfer == CoverageTransfer::Linear {
+            return;
+        }
+        let table = transfer.table();
+        self.iter_mut().for_each(|cr| cr.map_pixels(&table));
+    }
+
+    fn apply_dithering(&mut self, dithering: Dithering) {
+        if dithering == Dithering::None {
+            return;
+        }
+        self.iter_mut().for_each(|cr| cr.dither(dithering));
+    }
+
+    fn apply_effects(&mut self, effects: &EffectSettings) {
+        if !effects.is_active() {
+            return;
+        }
+        self.iter_mut().for_each(|cr| cr.apply_effects(effects));
+    }
+
+    fn apply_transform(&mut self, transform: GlyphTransform, center_y: f32) {
+        if !transform.moves_glyphs() {
+            return;
+        }
+        self.iter_mut().for_each(|cr| cr.transform(transform, center_y));
+    }
+
+    fn apply_inversion(&mut self, invert: bool) {
+        if !invert {
+            return;
+        }
+        // Every glyph box fits in this area, so it stands in for the lit cell.
+        let width = self.iter().map(|cr| cr.get_width()).max().unwrap_or(0);
+        let height = self.iter().map(|cr| cr.get_height()).max().unwrap_or(0);
+        self.iter_mut().for_each(|cr| cr.invert(255 * width * height));
+    }
 }
 
 
@@ -1,9 +1,14 @@
-use std::{collections::HashMap, fmt::Display, fs::File, io::BufWriter};
+use std::{collections::{HashMap, HashSet}, fmt::Display, fs::{self, File}, io::BufWriter, path::{Path, PathBuf}};
 
 use eframe::egui::ColorImage;
-use fontdue::LineMetrics;
+use fontdue::{LineMetrics, Metrics};
 
-use crate::rasterization::{RasterizationProperty, Rasterizations};
+use crate::{
+    effects::EffectSettings,
+    font_face::FontMetadata,
+    encoders::{encode_dds, encode_ktx2, encode_qoi, encode_tga},
+    rasterization::{CoverageTransfer, Dithering, DownsampleFilter, GlyphTransform, MissingGlyphPolicy, RasterizationProperty, Rasterizations},
+};
 
 
 #[derive(Debug)]
@@ -46,6 +51,22 @@ pub struct RenderSettings {
     pub sort_property: Option<RasterizationProperty>,
     pub dedup_property: Option<RasterizationProperty>,
     pub dedup_exact_duplicate: bool,
+    /// Coverage at or above which a pixel is set in 1-bit exports.
+    pub threshold: u8,
+    pub color_mode: ColorMode,
+    pub coverage_transfer: CoverageTransfer,
+    /// Glyphs are rasterized this many times larger and downsampled, 1 disables supersampling.
+    pub supersampling: usize,
+    pub downsample_filter: DownsampleFilter,
+    /// Synthetic bold, in pixels of horizontal smear.
+    pub embolden: f32,
+    /// Synthetic oblique slant, in degrees.
+    pub oblique_angle: f32,
+    /// Applied per glyph after the coverage transfer.
+    pub dithering: Dithering,
+    pub effects: EffectSettings,
+    pub transform: GlyphTransform,
+    pub missing_glyphs: MissingGlyphPolicy,
 }
 
 impl Default for RenderSettings {
@@ -59,6 +80,17 @@ impl Default for RenderSettings {
             sort_property: Some(RasterizationProperty::Brightness),
             dedup_property: Some(RasterizationProperty::Brightness),
             dedup_exact_duplicate: true,
+            threshold: 128,
+            color_mode: ColorMode::Grayscale,
+            coverage_transfer: CoverageTransfer::Linear,
+            supersampling: 1,
+            downsample_filter: DownsampleFilter::Box,
+            embolden: 0.0,
+            oblique_angle: 0.0,
+            dithering: Dithering::None,
+            effects: EffectSettings::default(),
+            transform: GlyphTransform::default(),
+            missing_glyphs: MissingGlyphPolicy::Notdef,
         }
     }
 }
@@ -149,12 +181,23 @@ impl Display for RenderLayout {
     }
 }
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(PartialEq, Clone, Copy, Default)]
 pub enum RenderDirection {
+    #[default]
     LeftToRight,
     TopToBottom,
 }
 
+impl RenderDirection {
+    /// Cell coordinate of the `idx`th glyph placed in a grid of `cell_h_count` by `cell_v_count` cells.
+    pub fn cell_position(self, idx: usize, cell_h_count: usize, cell_v_count: usize) -> (usize, usize) {
+        match self {
+            RenderDirection::LeftToRight => (idx % cell_h_count, idx / cell_h_count),
+            RenderDirection::TopToBottom => (idx / cell_v_count, idx % cell_v_count),
+        }
+    }
+}
+
 impl Display for RenderDirection {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -164,11 +207,122 @@ impl Display for RenderDirection {
     }
 }
 
+#[derive(PartialEq, Clone, Copy)]
+pub enum ColorMode {
+    /// Coverage as 8-bit gray over black.
+    Grayscale,
+    /// Foreground composited over background by coverage, colors are unmultiplied sRGBA.
+    Rgba {
+        foreground: [u8; 4],
+        background: [u8; 4],
+        premultiplied: bool,
+    },
+}
+
+impl ColorMode {
+    pub const RGBA: Self = Self::Rgba {
+        foreground: [255, 255, 255, 255],
+        background: [0, 0, 0, 0],
+        premultiplied: false,
+    };
+
+    /// Color of a pixel with the given coverage, as RGBA.
+    fn shade(self, coverage: u8) -> [u8; 4] {
+        let Self::Rgba { foreground, background, premultiplied } = self else {
+            return [coverage, coverage, coverage, 255];
+        };
+
+        let coverage = coverage as f32 / 255.0;
+        let fg_alpha = foreground[3] as f32 / 255.0 * coverage;
+        let bg_alpha = background[3] as f32 / 255.0;
+        let alpha = fg_alpha + bg_alpha * (1.0 - fg_alpha);
+
+        let mut rgba = [0u8; 4];
+        for channel in 0..3 {
+            let fg = foreground[channel] as f32 / 255.0;
+            let bg = background[channel] as f32 / 255.0;
+            let value = fg * fg_alpha + bg * bg_alpha * (1.0 - fg_alpha);
+            let value = if premultiplied {
+                value
+            } else if alpha > 0.0 {
+                value / alpha
+            } else {
+                // Fully transparent, keep the foreground color so filtering doesn't bleed dark fringes.
+                fg
+            };
+            rgba[channel] = (value * 255.0).round() as u8;
+        }
+        rgba[3] = (alpha * 255.0).round() as u8;
+        rgba
+    }
+}
+
+impl Display for ColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Grayscale => write!(f, "Grayscale"),
+            Self::Rgba { .. } => write!(f, "RGBA"),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum BitDepth {
+    One,
+    Two,
+    Four,
+    #[default]
+    Eight,
+}
+
+impl BitDepth {
+    pub fn bits(self) -> usize {
+        match self {
+            Self::One => 1,
+            Self::Two => 2,
+            Self::Four => 4,
+            Self::Eight => 8,
+        }
+    }
+
+    /// Scales an 8-bit coverage value down to the nearest level this depth can hold.
+    pub fn quantize(self, value: u8) -> u8 {
+        let max = (1u16 << self.bits()) - 1;
+        ((value as u16 * max + 127) / 255) as u8
+    }
+
+    /// Like `quantize`, except 1 bpp sets values at or above `threshold` instead of cutting at half coverage.
+    pub fn quantize_at(self, value: u8, threshold: u8) -> u8 {
+        match self {
+            Self::One => (value >= threshold) as u8,
+            _ => self.quantize(value),
+        }
+    }
+
+    /// Smallest depth able to index `levels` distinct values.
+    pub fn for_levels(levels: usize) -> Self {
+        match levels {
+            0..=2 => Self::One,
+            3..=4 => Self::Two,
+            5..=16 => Self::Four,
+            _ => Self::Eight,
+        }
+    }
+}
+
+impl Display for BitDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} bpp", self.bits())
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct RenderData {
     width: usize,
     height: usize,
     pixels: Vec<u8>,
+    encoding: PixelEncoding,
+    effect: Option<Vec<u8>>,
 }
 
 impl RenderData {
@@ -177,6 +331,190 @@ impl RenderData {
     }
     pub fn width(&self) -> usize {self.width}
     pub fn height(&self) -> usize {self.height}
+    pub fn pixels(&self) -> &[u8] {&self.pixels}
+
+    /// Pixels as RGBA, shaded by `color_mode`.
+    pub fn rgba_pixels(&self, color_mode: ColorMode) -> Vec<u8> {
+        self.pixels.iter().flat_map(|v| color_mode.shade(*v)).collect()
+    }
+
+    pub fn encoding(&self) -> PixelEncoding {self.encoding}
+    pub fn effect(&self) -> Option<&[u8]> {self.effect.as_deref()}
+
+    /// Glyph coverage in red and effect coverage in green, for renders with a separate effect channel.
+    fn effect_rgba(&self, effect: &[u8]) -> Vec<u8> {
+        self.pixels.iter().zip(effect).flat_map(|(v, e)| [*v, *e, 0, 255]).collect()
+    }
+
+    /// Image to write for the given color mode. RGBA output mixes sRGB colors, so it is tagged as sRGB.
+    ///
+    /// Renders with a separate effect channel are written as data textures instead, see `effect_rgba`.
+    pub fn to_output(&self, color_mode: ColorMode) -> OutputImage {
+        if let Some(effect) = &self.effect {
+            return OutputImage::new(self.width, self.height, PixelFormat::Rgba8, self.effect_rgba(effect))
+                .with_encoding(self.encoding);
+        }
+        match color_mode {
+            ColorMode::Grayscale => OutputImage::new(self.width, self.height, PixelFormat::Gray8, self.pixels.clone())
+                .with_encoding(self.encoding),
+            ColorMode::Rgba { .. } => OutputImage::new(self.width, self.height, PixelFormat::Rgba8, self.rgba_pixels(color_mode))
+                .with_encoding(PixelEncoding::Srgb),
+        }
+    }
+
+    /// Image shown in the app, transparent areas are drawn over a checkerboard.
+    pub fn preview(&self, color_mode: ColorMode) -> ColorImage {
+        if let Some(effect) = &self.effect {
+            let rgb = self.pixels.iter().zip(effect).flat_map(|(v, e)| [*v, *e, 0]).collect::<Vec<_>>();
+            return ColorImage::from_rgb([self.width, self.height], &rgb);
+        }
+        if color_mode == ColorMode::Grayscale {
+            return ColorImage::from(self.clone());
+        }
+
+        let premultiplied = matches!(color_mode, ColorMode::Rgba { premultiplied: true, .. });
+        let mut rgb = Vec::with_capacity(self.pixels.len() * 3);
+        for (i, value) in self.pixels.iter().enumerate() {
+            let (x, y) = (i % self.width, i / self.width);
+            let checker = if (x / 4 + y / 4) % 2 == 0 { 204.0 } else { 153.0 };
+            let [r, g, b, a] = color_mode.shade(*value);
+            let alpha = a as f32 / 255.0;
+            for channel in [r, g, b] {
+                let channel = if premultiplied {
+                    channel as f32
+                } else {
+                    channel as f32 * alpha
+                };
+                rgb.push((channel + checker * (1.0 - alpha)).round() as u8);
+            }
+        }
+        ColorImage::from_rgb([self.width, self.height], &rgb)
+    }
+}
+
+/// How stored values relate to linear light, used to tag exported files.
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum PixelEncoding {
+    #[default]
+    Linear,
+    /// Values are `light^exponent`, as in a PNG gAMA chunk.
+    Gamma(f32),
+    Srgb,
+}
+
+impl From<CoverageTransfer> for PixelEncoding {
+    fn from(value: CoverageTransfer) -> Self {
+        match value {
+            // The contrast curve reshapes coverage but still treats it as linear light.
+            CoverageTransfer::Linear | CoverageTransfer::Contrast(_) => Self::Linear,
+            CoverageTransfer::Gamma(gamma) => Self::Gamma(1.0 / gamma),
+            CoverageTransfer::LinearToSrgb => Self::Srgb,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum PixelFormat {
+    Gray8,
+    Rgba8,
+}
+
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    Tga,
+    Dds,
+    Ktx2,
+    Qoi,
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Tga => "tga",
+            Self::Dds => "dds",
+            Self::Ktx2 => "ktx2",
+            Self::Qoi => "qoi",
+        }
+    }
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Png => write!(f, "PNG"),
+            Self::Tga => write!(f, "TGA"),
+            Self::Dds => write!(f, "DDS"),
+            Self::Ktx2 => write!(f, "KTX2"),
+            Self::Qoi => write!(f, "QOI"),
+        }
+    }
+}
+
+/// Pixels ready to be encoded, as opposed to the coverage held by `RenderData`.
+#[derive(Clone)]
+pub struct OutputImage {
+    pub width: usize,
+    pub height: usize,
+    pub format: PixelFormat,
+    pub pixels: Vec<u8>,
+    pub encoding: PixelEncoding,
+}
+
+impl OutputImage {
+    pub fn new(width: usize, height: usize, format: PixelFormat, pixels: Vec<u8>) -> Self {
+        Self { width, height, format, pixels, encoding: PixelEncoding::Linear }
+    }
+
+    pub fn with_encoding(mut self, encoding: PixelEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Pixels as RGBA, grayscale is expanded to opaque gray.
+    pub fn rgba(&self) -> Box<dyn Iterator<Item = [u8; 4]> + '_> {
+        match self.format {
+            PixelFormat::Gray8 => Box::new(self.pixels.iter().map(|v| [*v, *v, *v, 255])),
+            PixelFormat::Rgba8 => Box::new(self.pixels.chunks_exact(4).map(|p| [p[0], p[1], p[2], p[3]])),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct OutputSettings {
+    pub format: OutputFormat,
+    /// Bit depth of grayscale PNGs.
+    pub bit_depth: BitDepth,
+    /// Colors of an indexed PNG, every pixel is mapped to the closest entry.
+    pub palette: Option<Vec<[u8; 4]>>,
+}
+
+impl OutputSettings {
+    /// Number of distinct levels in `image` and how many of them the PNG settings can keep,
+    /// when that is fewer.
+    pub fn lost_levels(&self, image: &OutputImage) -> Option<(usize, usize)> {
+        if self.format != OutputFormat::Png {
+            return None;
+        }
+        let representable = if let Some(palette) = &self.palette {
+            palette.len()
+        } else if image.format == PixelFormat::Gray8 && self.bit_depth != BitDepth::Eight {
+            1 << self.bit_depth.bits()
+        } else {
+            return None;
+        };
+        let levels = if image.format == PixelFormat::Gray8 {
+            let mut seen = [false; 256];
+            image.pixels.iter().for_each(|v| seen[*v as usize] = true);
+            seen.iter().filter(|s| **s).count()
+        } else {
+            // Colors outnumber a lookup table, only palettes get here.
+            image.rgba().collect::<HashSet<_>>().len()
+        };
+        (levels > representable).then_some((levels, representable))
+    }
 }
 
 impl From<RenderData> for ColorImage {
@@ -191,7 +529,11 @@ pub struct RenderInfo {
     cell_size: (usize, usize),
     cell_count: (usize, usize),
     cell_filled: usize,
-    cell_padding: (usize, usize, usize, usize)
+    cell_padding: (usize, usize, usize, usize),
+    render_direction: RenderDirection,
+    cell_glyphs: Vec<(char, Metrics)>,
+    baseline: usize,
+    missing_chars: Vec<char>,
 }
 
 impl RenderInfo {
@@ -199,6 +541,30 @@ impl RenderInfo {
     pub fn cell_count(&self) -> (usize, usize) {self.cell_count}
     pub fn cell_filled(&self) -> usize {self.cell_filled}
     pub fn cell_padding(&self) -> (usize, usize, usize, usize) {self.cell_padding}
+    pub fn render_direction(&self) -> RenderDirection {self.render_direction}
+    /// Character and metrics of each filled cell, in placement order.
+    pub fn cell_glyphs(&self) -> &[(char, Metrics)] {&self.cell_glyphs}
+    /// Distance in pixels from the top of a cell to the glyph baseline.
+    pub fn baseline(&self) -> usize {self.baseline}
+    /// Input characters no font in the stack maps.
+    pub fn missing_chars(&self) -> &[char] {&self.missing_chars}
+
+    pub fn with_missing_chars(mut self, missing_chars: Vec<char>) -> Self {
+        self.missing_chars = missing_chars;
+        self
+    }
+
+    /// Pixel coordinate of the top left corner of the `idx`th cell, padding excluded.
+    pub fn cell_origin(&self, idx: usize) -> (usize, usize) {
+        let (cell_width, cell_height) = self.cell_size;
+        let (cell_h_count, cell_v_count) = self.cell_count;
+        let (left, right, up, down) = self.cell_padding;
+        let (cell_x, cell_y) = self.render_direction.cell_position(idx, cell_h_count, cell_v_count);
+        (
+            cell_x * (cell_width + left + right) + left,
+            cell_y * (cell_height + up + down) + up,
+        )
+    }
 }
 
 
@@ -209,8 +575,11 @@ pub fn generate_render_data(
     render_settings: &RenderSettings
 ) -> (RenderData, RenderInfo) {
 
-    let (vascent, vdescent) = if let Some(l_m) = h_line_metrics {
-        (l_m.ascent, l_m.descent)
+    // Effects grew every glyph bitmap, the line metrics grow the same way so nothing is clipped.
+    let (left, right, up, down) = render_settings.effects.extent();
+
+    let (mut vascent, vdescent) = if let Some(l_m) = h_line_metrics {
+        (l_m.ascent + up as f32, l_m.descent - down as f32)
     } else {
         (
             rasterizations
@@ -223,7 +592,7 @@ pub fn generate_render_data(
     };
 
     let (hascent, hdescent) = if let Some(l_m) = v_line_metrics {
-        (l_m.ascent, l_m.descent)
+        (l_m.ascent + left as f32, l_m.descent - right as f32)
     } else {
         (
             rasterizations
@@ -235,8 +604,24 @@ pub fn generate_render_data(
         )
     };
 
-    let cell_width = (hascent - hdescent).round() as usize;
-    let cell_height = (vascent - vdescent).round() as usize;
+    // Bold, oblique and turned glyphs can be wider than the vertical line metrics, the cell grows to fit.
+    let cell_width = rasterizations
+        .iter()
+        .map(|cr| cr.get_width())
+        .fold((hascent - hdescent).round() as usize, usize::max);
+    let mut cell_height = (vascent - vdescent).round() as usize;
+
+    // Rotated and mirrored glyphs can leave the line metrics, the cell grows to their transformed boxes.
+    if render_settings.transform.moves_glyphs() {
+        vascent = rasterizations
+            .iter()
+            .map(|cr| cr.get_metrics().height as f32 + cr.get_metrics().bounds.ymin)
+            .fold(vascent, f32::max);
+        cell_height = rasterizations
+            .iter()
+            .map(|cr| (vascent - cr.get_metrics().bounds.ymin).ceil() as usize)
+            .fold((vascent - vdescent).round() as usize, usize::max);
+    }
 
     let padded_cell_width = cell_width + render_settings.render_padding.horizontal();
     let padded_cell_height = cell_height + render_settings.render_padding.vertical();
@@ -255,32 +640,36 @@ pub fn generate_render_data(
     let texture_height = cell_v_count * padded_cell_height;
 
     let mut pixels = vec![0u8; texture_width * texture_height];
+    let mut effect = render_settings.effects.writes_channel().then(|| vec![0u8; texture_width * texture_height]);
+    let mut cell_glyphs = Vec::with_capacity(cell_count.min(raster_count));
 
     for (idx, rasterization) in rasterizations.into_iter().enumerate() {
 
         let metrics = rasterization.get_metrics();
-        let rasterization = rasterization.get_pixels();
 
         // let xmin = metrics.bounds.xmin.round() as isize;
         let ymin = metrics.bounds.ymin;
 
         let inverted_ymin = (vascent - ((metrics.height as f32) + ymin)).ceil() as isize;
-        let width_offset = ((cell_width - metrics.width) as f32 / 2.0).ceil() as isize;
+        let width_offset = (cell_width.saturating_sub(metrics.width) as f32 / 2.0).ceil() as isize;
 
-        let (cell_x, cell_y) = match render_settings.render_direction {
-            RenderDirection::LeftToRight => {
-                let x = idx % cell_h_count;
-                let y = idx / cell_h_count;
-                (x, y)
-            },
-            RenderDirection::TopToBottom => {
-                let x = idx / cell_v_count;
-                let y = idx % cell_v_count;
-                (x, y)
+        let (cell_x, cell_y) = render_settings.render_direction.cell_position(idx, cell_h_count, cell_v_count);
+
+        if idx < cell_count {
+            cell_glyphs.push((rasterization.get_char(), metrics));
+
+            // Inverted glyphs only cover their own box, the rest of the cell is lit here.
+            if render_settings.transform.invert {
+                for y in 0..cell_height {
+                    let row = (cell_y * padded_cell_height + render_settings.render_padding.up + y) * texture_width;
+                    let start = row + cell_x * padded_cell_width + render_settings.render_padding.left;
+                    pixels[start..start + cell_width].fill(255);
+                }
             }
-        };
+        }
 
-        for (i, value) in rasterization.iter().enumerate() {
+        let glyph_effect = rasterization.get_effect();
+        for (i, value) in rasterization.get_pixels().iter().enumerate() {
             // Pixel coordinate within character rasterization.
             let raster_relative_x = i % metrics.width;
             let raster_relative_y = (i - raster_relative_x) / metrics.width;
@@ -305,6 +694,11 @@ pub fn generate_render_data(
             if let Some(pixel) = pixels.get_mut(index) {
                 *pixel = *value;
             } 
+            if let (Some(effect), Some(glyph_effect)) = (effect.as_mut(), glyph_effect) {
+                if let Some(pixel) = effect.get_mut(index) {
+                    *pixel = glyph_effect[i];
+                }
+            }
         }
 
     }
@@ -313,7 +707,9 @@ pub fn generate_render_data(
         RenderData {
             width: texture_width,
             height: texture_height,
-            pixels
+            pixels,
+            encoding: render_settings.coverage_transfer.into(),
+            effect,
         },
         RenderInfo {
             cell_count: (cell_h_count, cell_v_count),
@@ -324,38 +720,175 @@ pub fn generate_render_data(
                 render_settings.render_padding.right,
                 render_settings.render_padding.up,
                 render_settings.render_padding.down,
-            )
+            ),
+            render_direction: render_settings.render_direction,
+            cell_glyphs,
+            baseline: vascent.ceil() as usize,
+            missing_chars: vec![],
         }
     )
 }
 
 
 
-pub fn write_image(name: String, render_data: &RenderData) -> Result<(), RendererError> {
+/// Turns a font stem into an upper case identifier usable in generated source, e.g. `Pixel-Operator` -> `PIXEL_OPERATOR`.
+pub fn const_identifier(stem: &str) -> String {
+    let name = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect::<String>();
+    if name.starts_with(|c: char| c.is_ascii_digit()) || name.is_empty() {
+        format!("FONT_{name}")
+    } else {
+        name
+    }
+}
 
-    let width = render_data.width;
-    let height = render_data.height;
-    let pixels = &render_data.pixels;
+/// Packs values of `depth` bits into bytes, most significant bits first, each row starting on a new byte.
+pub fn pack_rows(values: &[u8], width: usize, depth: BitDepth) -> Vec<u8> {
+    if depth == BitDepth::Eight {
+        return values.to_vec();
+    }
+    let bits = depth.bits();
+    let mut packed = vec![];
+    for row in values.chunks(width.max(1)) {
+        let mut bytes = vec![0u8; (width * bits).div_ceil(8)];
+        for (i, value) in row.iter().enumerate() {
+            bytes[i * bits / 8] |= value << (8 - bits - (i * bits) % 8);
+        }
+        packed.extend(bytes);
+    }
+    packed
+}
 
-    let render_path = rfd::FileDialog::new()
+/// Packs set bits into bytes, most significant bit first, each row of `row_width` bits starting
+/// on a new byte. A row width spanning all of `bits` packs them back to back.
+pub fn pack_bits(bits: &[bool], row_width: usize) -> Vec<u8> {
+    let levels = bits.iter().map(|set| *set as u8).collect::<Vec<_>>();
+    pack_rows(&levels, row_width, BitDepth::One)
+}
+
+/// Index of the palette entry closest to `color`.
+fn nearest_color(palette: &[[u8; 4]], color: [u8; 4]) -> usize {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, entry)| {
+            entry
+                .iter()
+                .zip(color)
+                .map(|(a, b)| (*a as i32 - b as i32).pow(2))
+                .sum::<i32>()
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+/// Parses a palette written as hex colors, e.g. `#000000 #ffffff80`.
+pub fn parse_palette(text: &str) -> Option<Vec<[u8; 4]>> {
+    let palette = text
+        .split([' ', ',', '\n'])
+        .filter(|c| !c.is_empty())
+        .map(|c| {
+            let hex = c.trim_start_matches('#');
+            let channel = |i: usize| hex.get(i..i + 2).and_then(|h| u8::from_str_radix(h, 16).ok());
+            match hex.len() {
+                6 => Some([channel(0)?, channel(2)?, channel(4)?, 255]),
+                8 => Some([channel(0)?, channel(2)?, channel(4)?, channel(6)?]),
+                _ => None,
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (!palette.is_empty() && palette.len() <= 256).then_some(palette)
+}
+
+/// Asks the user where to save an export named `name`, suggesting the given extension.
+pub fn pick_save_path(name: String, filter: &str, extension: &str) -> Result<PathBuf, RendererError> {
+    rfd::FileDialog::new()
         .set_directory("/")
-        .add_filter("png", &["png"])
-        .set_file_name(name)
+        .add_filter(filter, &[extension])
+        .set_file_name(format!("{name}.{extension}"))
         .save_file()
-        .ok_or(RendererError::InvalidPath)?;
+        .ok_or(RendererError::InvalidPath)
+}
 
-    println!("{}", render_path.to_str().unwrap());
+/// Writes the font's names and license next to the export at `path`, as a `.txt` file.
+pub fn write_font_notice(path: &Path, metadata: &FontMetadata) -> Result<(), RendererError> {
+    let export_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    fs::write(path.with_extension("txt"), metadata.generate_notice_file(export_name))
+        .map_err(|_| RendererError::CreationError)
+}
+
+/// Writes `image` in the chosen format, 1 bpp PNGs set pixels at or above `threshold`.
+pub fn write_image(name: String, image: &OutputImage, output_settings: &OutputSettings, threshold: u8) -> Result<PathBuf, RendererError> {
+
+    let format = output_settings.format;
+    let render_path = pick_save_path(name, format.extension(), format.extension())?;
 
     println!("Trying to create file at {}", render_path.display());
 
-    let file = File::create(render_path).map_err(|_| RendererError::CreationError)?;
+    let encoded = match format {
+        OutputFormat::Png => return write_png(render_path, image, output_settings, threshold),
+        OutputFormat::Tga => encode_tga(image)?,
+        OutputFormat::Dds => encode_dds(image)?,
+        OutputFormat::Ktx2 => encode_ktx2(image)?,
+        OutputFormat::Qoi => encode_qoi(image)?,
+    };
+
+    fs::write(&render_path, encoded).map_err(|_| RendererError::CreationError)?;
+
+    Ok(render_path)
+}
+
+fn write_png(render_path: PathBuf, image: &OutputImage, output_settings: &OutputSettings, threshold: u8) -> Result<PathBuf, RendererError> {
+
+    let width = image.width;
+    let height = image.height;
+
+    let (color_type, depth, pixels, palette) = if let Some(palette) = &output_settings.palette {
+        let depth = BitDepth::for_levels(palette.len());
+        let indices = image.rgba().map(|px| nearest_color(palette, px) as u8).collect::<Vec<_>>();
+        (png::ColorType::Indexed, depth, pack_rows(&indices, width, depth), Some(palette))
+    } else if image.format == PixelFormat::Gray8 {
+        let depth = output_settings.bit_depth;
+        let levels = image.pixels.iter().map(|v| depth.quantize_at(*v, threshold)).collect::<Vec<_>>();
+        (png::ColorType::Grayscale, depth, pack_rows(&levels, width, depth), None)
+    } else {
+        (png::ColorType::Rgba, BitDepth::Eight, image.pixels.clone(), None)
+    };
+
+    let file = File::create(&render_path).map_err(|_| RendererError::CreationError)?;
     let ref mut writer = BufWriter::new(file);
 
     let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
-    encoder.set_color(png::ColorType::Grayscale);
-    encoder.set_depth(png::BitDepth::Eight);
-    encoder.set_source_gamma(png::ScaledFloat::from_scaled(45455)); // 1.0 / 2.2, scaled by 100000
-    encoder.set_source_gamma(png::ScaledFloat::new(1.0 / 2.2)); // 1.0 / 2.2, unscaled, but rounded
+    encoder.set_color(color_type);
+    encoder.set_depth(match depth {
+        BitDepth::One => png::BitDepth::One,
+        BitDepth::Two => png::BitDepth::Two,
+        BitDepth::Four => png::BitDepth::Four,
+        BitDepth::Eight => png::BitDepth::Eight,
+    });
+    if let Some(palette) = palette {
+        encoder.set_palette(palette.iter().flat_map(|c| [c[0], c[1], c[2]]).collect::<Vec<_>>());
+        if palette.iter().any(|c| c[3] < 255) {
+            encoder.set_trns(palette.iter().map(|c| c[3]).collect::<Vec<_>>());
+        }
+    }
+    // Indexed palettes are picked as sRGB colors, whatever the coverage encoding.
+    let encoding = if palette.is_some() {
+        PixelEncoding::Srgb
+    } else {
+        image.encoding
+    };
+    match encoding {
+        PixelEncoding::Linear => encoder.set_source_gamma(png::ScaledFloat::new(1.0)),
+        PixelEncoding::Gamma(exponent) => encoder.set_source_gamma(png::ScaledFloat::new(exponent)),
+        PixelEncoding::Srgb => {
+            encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+            // Fallback gAMA for decoders that ignore the sRGB chunk.
+            encoder.set_source_gamma(png::ScaledFloat::from_scaled(45455));
+        },
+    }
     let source_chromaticities = png::SourceChromaticities::new(
         // Using unscaled instantiation here
         (0.31270, 0.32900),
@@ -366,7 +899,7 @@ pub fn write_image(name: String, render_data: &RenderData) -> Result<(), Rendere
     encoder.set_source_chromaticities(source_chromaticities);
 
     let mut writer = encoder.write_header().unwrap();
-    writer.write_image_data(pixels).unwrap();
+    writer.write_image_data(&pixels).unwrap();
 
-    Ok(())
+    Ok(render_path)
 }
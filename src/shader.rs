@@ -0,0 +1,98 @@
+use std::{fmt::Display, fs};
+
+use crate::renderer::{pick_save_path, RenderDirection, RenderInfo, RendererError};
+
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum ShaderLanguage {
+    #[default]
+    Glsl,
+    Wgsl,
+    Godot,
+}
+
+impl ShaderLanguage {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Glsl => "glsl",
+            Self::Wgsl => "wgsl",
+            Self::Godot => "gdshaderinc",
+        }
+    }
+}
+
+impl Display for ShaderLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Glsl => write!(f, "GLSL"),
+            Self::Wgsl => write!(f, "WGSL"),
+            Self::Godot => write!(f, "Godot"),
+        }
+    }
+}
+
+pub fn generate_shader(render_info: &RenderInfo, language: ShaderLanguage) -> String {
+    let (cell_width, cell_height) = render_info.cell_size();
+    let (cell_h_count, cell_v_count) = render_info.cell_count();
+    let (left, right, up, down) = render_info.cell_padding();
+    let glyph_count = render_info.cell_filled();
+
+    let texture_width = cell_h_count * (cell_width + left + right);
+    let texture_height = cell_v_count * (cell_height + up + down);
+
+    // Cell coordinate of a glyph index, following the order glyphs were placed in.
+    let cell = match (language, render_info.render_direction()) {
+        (ShaderLanguage::Wgsl, RenderDirection::LeftToRight) => "vec2<f32>(index % CELL_COUNT.x, floor(index / CELL_COUNT.x))",
+        (ShaderLanguage::Wgsl, RenderDirection::TopToBottom) => "vec2<f32>(floor(index / CELL_COUNT.y), index % CELL_COUNT.y)",
+        (_, RenderDirection::LeftToRight) => "vec2(mod(index, CELL_COUNT.x), floor(index / CELL_COUNT.x))",
+        (_, RenderDirection::TopToBottom) => "vec2(floor(index / CELL_COUNT.y), mod(index, CELL_COUNT.y))",
+    };
+
+    let header = format!(
+        "// Generated by Font Rasterizer.\n\
+        // Atlas: {texture_width}x{texture_height} pixels, {cell_h_count}x{cell_v_count} cells of {cell_width}x{cell_height} pixels, {glyph_count} glyphs.\n\
+        // glyph_uv_rect maps a 0-1 brightness to the UV rect (xy: origin, zw: size) of the matching glyph.\n"
+    );
+
+    match language {
+        ShaderLanguage::Glsl | ShaderLanguage::Godot => format!(
+            "{header}\n\
+            const vec2 ATLAS_SIZE = vec2({texture_width}.0, {texture_height}.0);\n\
+            const vec2 CELL_SIZE = vec2({cell_width}.0, {cell_height}.0);\n\
+            const vec2 CELL_COUNT = vec2({cell_h_count}.0, {cell_v_count}.0);\n\
+            const vec4 CELL_PADDING = vec4({left}.0, {right}.0, {up}.0, {down}.0); // left, right, up, down\n\
+            const int GLYPH_COUNT = {glyph_count};\n\
+            \n\
+            vec4 glyph_uv_rect(float brightness) {{\n\
+            \x20   float last = max(float(GLYPH_COUNT) - 1.0, 0.0);\n\
+            \x20   float index = clamp(round(brightness * last), 0.0, last);\n\
+            \x20   vec2 cell = {cell};\n\
+            \x20   vec2 padded_cell_size = CELL_SIZE + CELL_PADDING.xz + CELL_PADDING.yw;\n\
+            \x20   vec2 origin = cell * padded_cell_size + CELL_PADDING.xz;\n\
+            \x20   return vec4(origin / ATLAS_SIZE, CELL_SIZE / ATLAS_SIZE);\n\
+            }}\n"
+        ),
+        ShaderLanguage::Wgsl => format!(
+            "{header}\n\
+            const ATLAS_SIZE: vec2<f32> = vec2<f32>({texture_width}.0, {texture_height}.0);\n\
+            const CELL_SIZE: vec2<f32> = vec2<f32>({cell_width}.0, {cell_height}.0);\n\
+            const CELL_COUNT: vec2<f32> = vec2<f32>({cell_h_count}.0, {cell_v_count}.0);\n\
+            const CELL_PADDING: vec4<f32> = vec4<f32>({left}.0, {right}.0, {up}.0, {down}.0); // left, right, up, down\n\
+            const GLYPH_COUNT: u32 = {glyph_count}u;\n\
+            \n\
+            fn glyph_uv_rect(brightness: f32) -> vec4<f32> {{\n\
+            \x20   let last = max(f32(GLYPH_COUNT) - 1.0, 0.0);\n\
+            \x20   let index = clamp(round(brightness * last), 0.0, last);\n\
+            \x20   let cell = {cell};\n\
+            \x20   let padded_cell_size = CELL_SIZE + CELL_PADDING.xz + CELL_PADDING.yw;\n\
+            \x20   let origin = cell * padded_cell_size + CELL_PADDING.xz;\n\
+            \x20   return vec4<f32>(origin / ATLAS_SIZE, CELL_SIZE / ATLAS_SIZE);\n\
+            }}\n"
+        ),
+    }
+}
+
+pub fn write_shader(name: String, render_info: &RenderInfo, language: ShaderLanguage) -> Result<(), RendererError> {
+    let shader_path = pick_save_path(name, &language.to_string(), language.extension())?;
+
+    fs::write(shader_path, generate_shader(render_info, language)).map_err(|_| RendererError::CreationError)
+}